@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use log::debug;
+use url::Url;
+
+use crate::exec::{
+    Request,
+    RequestError,
+};
+use crate::source::Engine;
+
+/// Reads content from the local filesystem.
+pub mod file;
+
+/// Retrieves content from web2 HTTP endpoints.
+#[cfg(feature = "web2")]
+pub mod http;
+
+/// An [Adapter] knows how to retrieve content from a specific type of endpoint.
+///
+/// Any function or closure with the same signature as [Adapter::fetch] is also an [Adapter].
+pub trait Adapter: Send + Sync {
+
+    /// Retrieve the content for the given [Request].
+    ///
+    /// Implementations that block for a long time should regularly check
+    /// [Request::is_cancelled] and abort if it returns `true`.
+    fn fetch(&self, request: &Request) -> Result<Vec<u8>, RequestError>;
+}
+
+impl<F> Adapter for F
+where F: Fn(&Request) -> Result<Vec<u8>, RequestError> + Send + Sync {
+    fn fetch(&self, request: &Request) -> Result<Vec<u8>, RequestError> {
+        self(request)
+    }
+}
+
+/// A lookup table of [Adapter]s used by the [Runner](crate::exec::Runner) to decide how to fetch
+/// each request.
+///
+/// Adapters may be registered for a [source::Engine](crate::source::Engine), for a URL scheme,
+/// or both. An adapter registered for the engine of a request takes precedence over one
+/// registered for the scheme of its URL.
+pub struct Registry {
+    engines: HashMap<Engine, Arc<dyn Adapter>>,
+    schemes: HashMap<String, Arc<dyn Adapter>>,
+}
+
+impl Default for Registry {
+    fn default() -> Registry {
+        Registry::new()
+    }
+}
+
+impl Registry {
+    pub fn new() -> Registry {
+        Registry {
+            engines: HashMap::new(),
+            schemes: HashMap::new(),
+        }
+    }
+
+    /// Register an [Adapter] for all requests of the given [Engine].
+    ///
+    /// Returns the adapter previously registered for the engine, if any.
+    pub fn add_engine(&mut self, engine: Engine, adapter: Arc<dyn Adapter>) -> Option<Arc<dyn Adapter>> {
+        debug!("added adapter for engine {}", engine);
+        self.engines.insert(engine, adapter)
+    }
+
+    /// Register an [Adapter] for all request URLs with the given scheme, e.g. `file` or `https`.
+    ///
+    /// Returns the adapter previously registered for the scheme, if any.
+    pub fn add_scheme(&mut self, scheme: &str, adapter: Arc<dyn Adapter>) -> Option<Arc<dyn Adapter>> {
+        debug!("added adapter for scheme {}", scheme);
+        self.schemes.insert(scheme.to_lowercase(), adapter)
+    }
+
+    /// Retrieve the [Adapter] to use for a request to `url` for the given [Engine].
+    pub fn adapter_for(&self, engine: &Engine, url: &str) -> Option<Arc<dyn Adapter>> {
+        if let Some(adapter) = self.engines.get(engine) {
+            return Some(adapter.clone());
+        }
+        match Url::parse(url) {
+            Ok(u) => self.schemes.get(u.scheme()).cloned(),
+            Err(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::Registry;
+    use crate::exec::Request;
+
+    #[test]
+    fn test_registry_lookup() {
+        let mut registry = Registry::new();
+        registry.add_scheme("mock", Arc::new(|_: &Request| Ok(vec![1])));
+        registry.add_engine("foo".to_string(), Arc::new(|_: &Request| Ok(vec![2])));
+
+        let request = Request::new(0, "mock://one/deadbeef".to_string(), "bar".to_string());
+        let adapter = registry.adapter_for(&request.engine, &request.url).unwrap();
+        assert_eq!(adapter.fetch(&request).unwrap(), vec![1]);
+
+        let request = Request::new(0, "mock://one/deadbeef".to_string(), "foo".to_string());
+        let adapter = registry.adapter_for(&request.engine, &request.url).unwrap();
+        assert_eq!(adapter.fetch(&request).unwrap(), vec![2]);
+
+        assert!(registry.adapter_for(&"bar".to_string(), "other://one/deadbeef").is_none());
+    }
+}
@@ -0,0 +1,120 @@
+use std::fs;
+use std::io::{
+    self,
+    Read,
+};
+
+use log::debug;
+use url::Url;
+
+use crate::adapter::Adapter;
+use crate::exec::{
+    Request,
+    RequestError,
+};
+
+const CHUNK_SIZE: usize = 65536;
+
+/// [FileAdapter] reads content from `file://` URLs on the local filesystem.
+///
+/// It can be used to include a local disk cache or a network mount as a source alongside
+/// remote ones.
+///
+/// If a maximum size is set, files larger than this are rejected with
+/// [RequestError::TooLarge].
+pub struct FileAdapter {
+    pub max_size: Option<u64>,
+}
+
+impl FileAdapter {
+    pub fn new(max_size: Option<u64>) -> FileAdapter {
+        FileAdapter {
+            max_size,
+        }
+    }
+}
+
+fn map_error(e: io::Error) -> RequestError {
+    match e.kind() {
+        io::ErrorKind::NotFound => RequestError::NotFound,
+        io::ErrorKind::PermissionDenied => RequestError::PermissionDenied,
+        _ => RequestError::Failed(e.to_string()),
+    }
+}
+
+impl Adapter for FileAdapter {
+    fn fetch(&self, request: &Request) -> Result<Vec<u8>, RequestError> {
+        let url = Url::parse(&request.url).map_err(|e| RequestError::Failed(e.to_string()))?;
+        if url.scheme() != "file" {
+            return Err(RequestError::Failed(format!("not a file url: {}", request.url)));
+        }
+        let path = url.to_file_path()
+            .map_err(|_| RequestError::Failed(format!("invalid file path: {}", request.url)))?;
+
+        let mut f = fs::File::open(&path).map_err(map_error)?;
+        let size = f.metadata().map_err(map_error)?.len();
+        if let Some(limit) = self.max_size {
+            if size > limit {
+                return Err(RequestError::TooLarge(limit));
+            }
+        }
+        debug!("reading {} bytes from {:?}", size, path);
+
+        let mut content: Vec<u8> = Vec::with_capacity(size as usize);
+        let mut buf = [0; CHUNK_SIZE];
+        loop {
+            if request.is_cancelled() {
+                return Err(RequestError::Cancelled);
+            }
+            let c = f.read(&mut buf).map_err(map_error)?;
+            if c == 0 {
+                break;
+            }
+            request.mark_first_byte();
+            content.extend_from_slice(&buf[..c]);
+            if let Some(limit) = self.max_size {
+                if content.len() as u64 > limit {
+                    return Err(RequestError::TooLarge(limit));
+                }
+            }
+        }
+        Ok(content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    use url::Url;
+
+    use super::FileAdapter;
+    use crate::adapter::Adapter;
+    use crate::exec::{
+        Request,
+        RequestError,
+    };
+
+    #[test]
+    fn test_file_adapter() {
+        let dir = env::temp_dir().join(format!("fadfada-file-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("deadbeef"), b"foo").unwrap();
+        let base = Url::from_file_path(&dir).unwrap();
+
+        let adapter = FileAdapter::new(None);
+        let request = Request::new(0, format!("{}/deadbeef", base), "file".to_string());
+        assert_eq!(adapter.fetch(&request).unwrap(), b"foo");
+
+        let request = Request::new(0, format!("{}/beeffeed", base), "file".to_string());
+        assert_eq!(adapter.fetch(&request), Err(RequestError::NotFound));
+
+        let adapter = FileAdapter::new(Some(2));
+        let request = Request::new(0, format!("{}/deadbeef", base), "file".to_string());
+        assert_eq!(adapter.fetch(&request), Err(RequestError::TooLarge(2)));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
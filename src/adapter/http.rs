@@ -0,0 +1,92 @@
+use std::error::Error as StdError;
+use std::io::{
+    self,
+    Read,
+};
+
+use log::debug;
+
+use crate::adapter::Adapter;
+use crate::exec::{
+    Request,
+    RequestError,
+};
+
+const CHUNK_SIZE: usize = 65536;
+
+/// [HttpAdapter] retrieves content from `http://` and `https://` URLs with a `GET` request.
+///
+/// The response body is read in chunks, so that the request can be aborted as soon as it is
+/// cancelled. The timeout of the [Request], if any, applies to the request as a whole.
+///
+/// Responses with a status code other than `2xx` are returned as [RequestError::Status].
+pub struct HttpAdapter {
+    agent: ureq::Agent,
+}
+
+impl Default for HttpAdapter {
+    fn default() -> HttpAdapter {
+        HttpAdapter::new()
+    }
+}
+
+impl HttpAdapter {
+    pub fn new() -> HttpAdapter {
+        HttpAdapter {
+            agent: ureq::AgentBuilder::new().build(),
+        }
+    }
+}
+
+fn is_timeout(e: &io::Error) -> bool {
+    matches!(e.kind(), io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock)
+}
+
+fn map_io_error(e: io::Error) -> RequestError {
+    match is_timeout(&e) {
+        true => RequestError::Timeout,
+        false => RequestError::Failed(e.to_string()),
+    }
+}
+
+fn map_error(e: ureq::Error) -> RequestError {
+    match e {
+        ureq::Error::Status(code, _) => RequestError::Status(code),
+        ureq::Error::Transport(t) => {
+            let timeout = t.source()
+                .and_then(|v| v.downcast_ref::<io::Error>())
+                .is_some_and(is_timeout);
+            match timeout {
+                true => RequestError::Timeout,
+                false => RequestError::Failed(t.to_string()),
+            }
+        },
+    }
+}
+
+impl Adapter for HttpAdapter {
+    fn fetch(&self, request: &Request) -> Result<Vec<u8>, RequestError> {
+        let mut req = self.agent.get(&request.url);
+        if let Some(timeout) = request.timeout {
+            req = req.timeout(timeout);
+        }
+        let response = req.call().map_err(map_error)?;
+        request.mark_first_byte();
+        debug!("http status {} for {}", response.status(), request.url);
+
+        let mut content: Vec<u8> = vec![];
+        let mut reader = response.into_reader();
+        let mut buf = [0; CHUNK_SIZE];
+        loop {
+            if request.is_cancelled() {
+                return Err(RequestError::Cancelled);
+            }
+            let c = reader.read(&mut buf).map_err(map_io_error)?;
+            if c == 0 {
+                break;
+            }
+            content.extend_from_slice(&buf[..c]);
+        }
+        Ok(content)
+    }
+}
@@ -1,25 +1,46 @@
+use std::cmp::Reverse;
+use std::time::Duration;
+
 use log::debug;
 
-use crate::source::Source;
+use crate::source::{
+    Source,
+    Start,
+};
 use crate::timing::Scheduler;
 use crate::resolver::Resolver;
+use crate::score::Scores;
+use crate::error::Error;
 
 pub mod graph;
-use graph::ControllerGraph;
+mod export;
+#[cfg(feature = "json")]
+mod json;
+use graph::{
+    ControllerGraph,
+    GraphNode,
+    Stagger,
+};
 
 /// Controller defines the order and scheduling of how a collection of sources are
 /// queried for content.
 ///
 /// If a delay is defined in the schedule, the same delay will apply to every added source.
 ///
-/// Each [Source] in turn defines its own delay applied to every [Endpoint] it contains.
+/// Each [Source] in turn defines its own delay applied to every [Endpoint] it contains. Sources
+/// without a [Scheduler] of their own use the schedule of the controller.
+///
+/// Requests scheduled at the same offset are dispatched together, unless a [Stagger] policy is
+/// set to space them apart.
 ///
 /// Once a controller has been populated, it can be used to generate [ControllerGraph] instances,
 /// which in turn can be used with a query engine to control the execution of a single query.
 pub struct Controller {
     pub sources: Vec<Source<'static>>,
     timing: Scheduler,
-    pub offsets: Vec<u32>,
+    /// Offset in milliseconds of the first request of every source.
+    pub offsets: Vec<u64>,
+    pub stagger: Stagger,
 }
 
 impl Controller {
@@ -28,51 +49,155 @@ impl Controller {
             sources: vec!(),
             offsets: vec!(),
             timing: scheduler,
+            stagger: Stagger::None,
         }
     }
 
+    /// The [Scheduler] applied to the sources of the controller.
+    pub fn timing(&self) -> &Scheduler {
+        &self.timing
+    }
+
+    /// Calculate the offset of the first request of every source.
+    fn update_offsets(&mut self) {
+        // delays are u32, so sums of them cannot overflow u64
+        let mut offsets: Vec<Option<u64>> = vec![None; self.sources.len()];
+        // A source may start after a source placed later in the list, so repeat until every
+        // offset that can be resolved has been.
+        loop {
+            let mut changed = false;
+            for i in 0..self.sources.len() {
+                if offsets[i].is_some() {
+                    continue;
+                }
+                offsets[i] = match &self.sources[i].start {
+                    Some(Start::Absolute(x)) => Some(*x as u64),
+                    Some(Start::After{engine, delay}) => {
+                        self.sources.iter()
+                            .position(|s| &s.engine == engine)
+                            .and_then(|j| offsets[j])
+                            .map(|x| x + *delay as u64)
+                    },
+                    None => match (i, self.timing.delay) {
+                        (0, _) | (_, 0) => Some(0),
+                        (_, x) => offsets[i-1].map(|y| y + x as u64),
+                    },
+                };
+                changed |= offsets[i].is_some();
+            }
+            if !changed {
+                break;
+            }
+        }
+        self.offsets = offsets.into_iter().map(|v| v.unwrap_or(0)).collect();
+    }
+
     /// Add a source to the request collection.
     ///
-    /// Sources will be requested in the order they were added.
+    /// Panics if the source cannot be added; see [Controller::try_add].
     pub fn add(&mut self, source: Source<'static>) {
-        match self.timing.delay {
-            x if x > 0 => match self.offsets.len() {
-                0 => {
-                    self.offsets.push(0);
-                },
-                y => {
-                    let u: u32 = self.offsets[y-1] + x;
-                    self.offsets.push(u);
-                }
-            },
-            _ => {
-                self.offsets.push(0);
-            },
+        self.try_add(source).unwrap();
+    }
+
+    /// Add a source to the request collection.
+    ///
+    /// Sources will be requested in order of priority, and then in the order they were added.
+    /// When two requests are scheduled at the same offset, the one from the source placed first
+    /// is dispatched first.
+    ///
+    /// Will error with [Error::UnknownEngine] if the source starts after an engine that has not
+    /// been added.
+    pub fn try_add(&mut self, source: Source<'static>) -> Result<(), Error> {
+        if let Some(Start::After{engine, ..}) = &source.start {
+            if !self.sources.iter().any(|s| &s.engine == engine) {
+                return Err(Error::UnknownEngine(engine.clone()));
+            }
         }
         debug!("controller added source {:?}", source);
-        self.sources.push(source);
+        let i = self.sources.iter()
+            .position(|s| s.priority < source.priority)
+            .unwrap_or(self.sources.len());
+        self.sources.insert(i, source);
+        self.update_offsets();
+        Ok(())
+    }
+
+    /// Reorder sources and endpoints by their [Scores], best first.
+    ///
+    /// Endpoints within each source are sorted by their score, and sources of the same priority
+    /// are sorted by the score of their best endpoint. Endpoints and sources without a score are
+    /// placed last, in their original order.
+    ///
+    /// The offsets of the sources are recalculated from the new order.
+    pub fn reorder(&mut self, scores: &Scores) {
+        let key = |v: Option<u64>| v.unwrap_or(u64::MAX);
+        for s in self.sources.iter_mut() {
+            s.endpoints.sort_by_key(|e| key(scores.value(e)));
+        }
+        self.sources.sort_by_key(|s| {
+            let score = s.endpoints.iter().map(|e| key(scores.value(e))).min().unwrap_or(u64::MAX);
+            (Reverse(s.priority), score)
+        });
+        self.update_offsets();
+        debug!("controller reordered sources {:?}", self.sources);
     }
 
     /// Generate a [ControllerGraph] from the current state of the [Controller].
+    ///
+    /// Sources whose engine has no entry in the [Resolver] are left out of the graph, and
+    /// listed in [ControllerGraph::skipped]. Use [Controller::try_generate] to treat them as an
+    /// error instead.
     pub fn generate(&mut self, resolver: &Resolver) -> ControllerGraph {
-        let mut g: ControllerGraph = ControllerGraph::new();
-        self.sources.iter().enumerate().for_each(|(i, s)| {
+        self.graph(resolver)
+    }
+
+    /// Generate a [ControllerGraph] from the current state of the [Controller].
+    ///
+    /// Will error with [Error::UnknownEngine] if the [Resolver] has no entry for the engine of a
+    /// source.
+    pub fn try_generate(&self, resolver: &Resolver) -> Result<ControllerGraph, Error> {
+        let g = self.graph(resolver);
+        match g.skipped().first() {
+            Some(v) => Err(Error::from(v.error.clone())),
+            None => Ok(g),
+        }
+    }
+
+    pub(crate) fn graph(&self, resolver: &Resolver) -> ControllerGraph {
+        let mut g: ControllerGraph = ControllerGraph::with_stagger(self.stagger);
+        for (i, s) in self.sources.iter().enumerate() {
             debug!("processing source {:?}", s);
-            s.endpoints.iter().enumerate().for_each(|(j, e)| {
-                let mut offset: u32 = self.offsets[i] as u32;
-                match &s.timing {
-                    Some(x) => {
-                        let pointer = resolver.pointer_for(&s.engine).unwrap();
-                        offset += x.delay * (j as u32);
-                        let pointer_url = e.url_for(&pointer);
-                        g.add(offset as u64, &s.engine, pointer_url); //.url_for(&pointer));
-                    },
-                    None => {},
-                }
-               // write!(f, "{} {} {} {}\n", i, j, offset, e);
-            });
-        });
-        g 
+            let x = s.timing.as_ref().unwrap_or(&self.timing);
+            let item = match resolver.item_for(&s.engine) {
+                Ok(v) => v,
+                Err(e) => {
+                    g.add_skipped(&s.engine, e);
+                    continue;
+                },
+            };
+            let pointer = item.pointer();
+            let signature = item.signature().ok().filter(|v| !v.is_empty());
+            let timeout = match x.timeout {
+                0 => None,
+                t => Some(Duration::from_millis(t as u64)),
+            };
+            for (j, e) in s.endpoints.iter().enumerate() {
+                let offset = self.offsets[i] + x.delay as u64 * j as u64;
+                g.add_node(GraphNode {
+                    offset,
+                    url: e.resolve(&pointer),
+                    engine: s.engine.clone(),
+                    timeout,
+                    validator: e.validator,
+                    trusted_keys: s.trusted_keys.clone(),
+                    digest: item.digest().clone(),
+                    signature: signature.clone(),
+                    source: Some(i),
+                    endpoint: Some(j),
+                });
+            }
+        }
+        g
     }
 
 }
@@ -86,4 +211,234 @@ impl Controller {
 
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
+    use super::Controller;
+    use super::graph::{
+        GraphNode,
+        Stagger,
+    };
+    use crate::endpoint::Endpoint;
+    use crate::error::Error;
+    use crate::resolver::{
+        ErrorDetail,
+        Resolver,
+    };
+    use crate::source::{
+        Source,
+        Start,
+    };
+    use crate::timing::Scheduler;
+    use crate::mock::{
+        TestResolverItem,
+        TestSignedResolverItem,
+        TestValidator,
+    };
+
+    fn controller() -> Controller {
+        let mut ctrl = Controller::new(Scheduler {
+            delay: 100,
+            timeout: 0,
+        });
+        for (engine, url) in [("foo", "https://one.foo.com"), ("bar", "https://only.bar.com")].iter() {
+            let mut source = Source::new(engine.to_string());
+            source.endpoints.push(Endpoint::new(url, None));
+            source.timing = Some(Scheduler {
+                delay: 0,
+                timeout: 0,
+            });
+            ctrl.add(source);
+        }
+        ctrl
+    }
+
+    #[test]
+    fn test_generate_skipped() {
+        let mut ctrl = controller();
+        let mut resolver = Resolver::new();
+        resolver.add("bar".to_string(), Box::new(TestResolverItem{key: vec![1, 2, 3]})).unwrap();
+
+        let g = ctrl.generate(&resolver);
+        assert_eq!(g.len(), 1);
+        assert_eq!(g.skipped().len(), 1);
+        assert_eq!(g.skipped()[0].engine, "foo");
+        assert_eq!(g.skipped()[0].error.detail(), &ErrorDetail::UnknownEngineError);
+        let v: Vec<(u64, String, String)> = g.into_iter().map(|v| (v.offset, v.url.to_string(), v.engine)).collect();
+        assert_eq!(v, vec![(100, "https://only.bar.com/010203".to_string(), "bar".to_string())]);
+    }
+
+    #[test]
+    fn test_generate_inherit_timing() {
+        let mut ctrl = Controller::new(Scheduler {
+            delay: 100,
+            timeout: 0,
+        });
+        let mut source_foo = Source::new("foo".to_string());
+        source_foo.endpoints.push(Endpoint::new("https://one.foo.com", None));
+        source_foo.endpoints.push(Endpoint::new("https://two.foo.com", None));
+        ctrl.add(source_foo);
+        let mut source_bar = Source::new("bar".to_string());
+        source_bar.endpoints.push(Endpoint::new("https://only.bar.com", None));
+        source_bar.timing = Some(Scheduler {
+            delay: 10,
+            timeout: 0,
+        });
+        ctrl.add(source_bar);
+
+        let mut resolver = Resolver::new();
+        resolver.add("foo".to_string(), Box::new(TestResolverItem{key: vec![1, 2, 3]})).unwrap();
+        resolver.add("bar".to_string(), Box::new(TestResolverItem{key: vec![4, 5, 6]})).unwrap();
+
+        let v: Vec<(u64, String, String)> = ctrl.generate(&resolver).into_iter().map(|v| (v.offset, v.url.to_string(), v.engine)).collect();
+        assert_eq!(v, vec![
+            (0, "https://one.foo.com/010203".to_string(), "foo".to_string()),
+            (100, "https://two.foo.com/010203".to_string(), "foo".to_string()),
+            (100, "https://only.bar.com/040506".to_string(), "bar".to_string()),
+        ]);
+
+        ctrl.stagger = Stagger::Spacing(5);
+        let v: Vec<u64> = ctrl.generate(&resolver).keys();
+        assert_eq!(v, vec![0, 100, 105]);
+    }
+
+    #[test]
+    fn test_generate_large_delay() {
+        let mut ctrl = Controller::new(Scheduler {
+            delay: 3000000000,
+            timeout: 0,
+        });
+        let mut source_foo = Source::new("foo".to_string());
+        for url in ["https://one.foo.com", "https://two.foo.com", "https://three.foo.com"].iter() {
+            source_foo.endpoints.push(Endpoint::new(url, None));
+        }
+        ctrl.add(source_foo);
+        let mut source_bar = Source::new("bar".to_string());
+        source_bar.endpoints.push(Endpoint::new("https://only.bar.com", None));
+        source_bar.start = Some(Start::After {
+            engine: "foo".to_string(),
+            delay: u32::MAX,
+        });
+        ctrl.add(source_bar);
+        assert_eq!(ctrl.offsets, vec![0, u32::MAX as u64]);
+
+        let mut resolver = Resolver::new();
+        resolver.add("foo".to_string(), Box::new(TestResolverItem{key: vec![1, 2, 3]})).unwrap();
+        resolver.add("bar".to_string(), Box::new(TestResolverItem{key: vec![4, 5, 6]})).unwrap();
+        let v: Vec<u64> = ctrl.generate(&resolver).keys();
+        assert_eq!(v, vec![0, 3000000000, u32::MAX as u64, 6000000000]);
+    }
+
+    #[test]
+    fn test_generate_nodes() {
+        static VALIDATOR: TestValidator = TestValidator{};
+        let mut ctrl = controller();
+        ctrl.sources[1].endpoints.push(Endpoint::new("https://other.bar.com", Some(&VALIDATOR)));
+        ctrl.sources[1].trusted_keys.push(vec![42]);
+        ctrl.sources[1].timing = Some(Scheduler {
+            delay: 10,
+            timeout: 300,
+        });
+        let mut resolver = Resolver::new();
+        resolver.add("foo".to_string(), Box::new(TestResolverItem{key: vec![1, 2, 3]})).unwrap();
+        resolver.add("bar".to_string(), Box::new(TestSignedResolverItem{key: vec![4, 5, 6], signature: vec![7]})).unwrap();
+
+        let g = ctrl.generate(&resolver);
+        let nodes: Vec<&GraphNode> = g.iter().collect();
+        assert_eq!(nodes.len(), 3);
+        assert_eq!(nodes[0].source, Some(0));
+        assert_eq!(nodes[0].timeout, None);
+        assert_eq!(nodes[0].signature, None);
+        assert_eq!(nodes[2].url.as_str(), "https://other.bar.com/040506");
+        assert_eq!(nodes[2].offset, 110);
+        assert_eq!(nodes[2].source, Some(1));
+        assert_eq!(nodes[2].endpoint, Some(1));
+        assert_eq!(nodes[2].timeout, Some(Duration::from_millis(300)));
+        assert_eq!(nodes[2].digest, vec![4, 5, 6]);
+        assert_eq!(nodes[2].signature, Some(vec![7]));
+        assert_eq!(nodes[2].trusted_keys, vec![vec![42]]);
+        assert!(nodes[2].validator.verify(&nodes[2].digest, Some(&vec![4, 5, 6]), None));
+        assert!(!nodes[2].validator.verify(&nodes[2].digest, Some(&vec![6]), None));
+    }
+
+    #[test]
+    fn test_generate_start() {
+        let mut ctrl = Controller::new(Scheduler {
+            delay: 0,
+            timeout: 0,
+        });
+        let mut source_swarm = Source::new("swarm".to_string());
+        source_swarm.endpoints.push(Endpoint::new("https://s1.swarm.org/bzz", None));
+        source_swarm.endpoints.push(Endpoint::new("https://s2.swarm.org/bzz", None));
+        source_swarm.timing = Some(Scheduler {
+            delay: 500,
+            timeout: 0,
+        });
+        ctrl.add(source_swarm);
+        let mut source_web2 = Source::new("web2".to_string());
+        source_web2.endpoints.push(Endpoint::new("https://w1.foo.com", None));
+        source_web2.endpoints.push(Endpoint::new("https://w2.foo.com", None));
+        source_web2.endpoints.push(Endpoint::new("https://w3.foo.com", None));
+        source_web2.timing = Some(Scheduler {
+            delay: 200,
+            timeout: 0,
+        });
+        source_web2.start = Some(Start::After {
+            engine: "swarm".to_string(),
+            delay: 400,
+        });
+        ctrl.add(source_web2);
+
+        let mut resolver = Resolver::new();
+        resolver.add("swarm".to_string(), Box::new(TestResolverItem{key: vec![1, 2, 3]})).unwrap();
+        resolver.add("web2".to_string(), Box::new(TestResolverItem{key: vec![4, 5, 6]})).unwrap();
+
+        let v: Vec<(u64, String)> = ctrl.generate(&resolver).into_iter().map(|v| (v.offset, v.url.to_string())).collect();
+        assert_eq!(v, vec![
+            (0, "https://s1.swarm.org/bzz/010203".to_string()),
+            (400, "https://w1.foo.com/040506".to_string()),
+            (500, "https://s2.swarm.org/bzz/010203".to_string()),
+            (600, "https://w2.foo.com/040506".to_string()),
+            (800, "https://w3.foo.com/040506".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn test_add_priority() {
+        let mut ctrl = controller();
+        let mut source = Source::new("baz".to_string());
+        source.priority = 1;
+        ctrl.add(source);
+        let mut source = Source::new("xyzzy".to_string());
+        source.start = Some(Start::Absolute(42));
+        ctrl.add(source);
+
+        let engines: Vec<&str> = ctrl.sources.iter().map(|s| s.engine.as_str()).collect();
+        assert_eq!(engines, vec!["baz", "foo", "bar", "xyzzy"]);
+        assert_eq!(ctrl.offsets, vec![0, 100, 200, 42]);
+
+        let mut source = Source::new("plugh".to_string());
+        source.start = Some(Start::After {
+            engine: "plugh".to_string(),
+            delay: 0,
+        });
+        let r = ctrl.try_add(source);
+        assert!(matches!(r, Err(Error::UnknownEngine(v)) if v == "plugh"));
+        assert_eq!(ctrl.sources.len(), 4);
+    }
+
+    #[test]
+    fn test_try_generate() {
+        let ctrl = controller();
+
+        let mut resolver = Resolver::new();
+        let r = ctrl.try_generate(&resolver);
+        assert!(matches!(r, Err(Error::UnknownEngine(v)) if v == "foo"));
+
+        resolver.add("foo".to_string(), Box::new(TestResolverItem{key: vec![1, 2, 3]})).unwrap();
+        let r = ctrl.try_generate(&resolver);
+        assert!(matches!(r, Err(Error::UnknownEngine(v)) if v == "bar"));
+
+        resolver.add("bar".to_string(), Box::new(TestResolverItem{key: vec![4, 5, 6]})).unwrap();
+        assert!(ctrl.try_generate(&resolver).is_ok());
+    }
 }
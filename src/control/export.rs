@@ -0,0 +1,123 @@
+use std::fmt::Write;
+
+use crate::source::Engine;
+
+use super::graph::ControllerGraph;
+
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+impl ControllerGraph {
+    /// Render the graph in the Graphviz DOT language.
+    ///
+    /// Nodes are grouped in a cluster per engine, and connected in the order they are
+    /// dispatched.
+    pub fn to_dot(&self) -> String {
+        let mut engines: Vec<&Engine> = vec![];
+        for node in self.iter() {
+            if !engines.contains(&&node.engine) {
+                engines.push(&node.engine);
+            }
+        }
+
+        let mut s = String::new();
+        let _r = writeln!(s, "digraph fadfada {{");
+        let _r = writeln!(s, "\trankdir=LR;");
+        for (i, engine) in engines.iter().enumerate() {
+            let _r = writeln!(s, "\tsubgraph cluster_{} {{", i);
+            let _r = writeln!(s, "\t\tlabel=\"{}\";", dot_escape(engine));
+            for (j, node) in self.iter().enumerate() {
+                if &node.engine == *engine {
+                    let _r = writeln!(s, "\t\tn{} [label=\"+{}ms\\n{}\"];", j, node.offset, dot_escape(node.url.as_str()));
+                }
+            }
+            let _r = writeln!(s, "\t}}");
+        }
+        for j in 1..self.len() {
+            let _r = writeln!(s, "\tn{} -> n{};", j - 1, j);
+        }
+        let _r = writeln!(s, "}}");
+        s
+    }
+
+    /// Render the graph as a text table with a timeline of `width` characters.
+    ///
+    /// On the timeline, `|` marks when a request is dispatched, and `=` the time until it times
+    /// out. Requests without a timeout are drawn with `-` until the end of the timeline.
+    pub fn to_timeline(&self, width: usize) -> String {
+        let width = width.max(1);
+        let end = self.iter()
+            .map(|v| v.offset + v.timeout.map_or(0, |t| t.as_millis() as u64))
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        let engine_width = self.iter().map(|v| v.engine.len()).max().unwrap_or(0).max(6);
+        let url_width = self.iter().map(|v| v.url.as_str().len()).max().unwrap_or(0).max(3);
+        let column = |x: u64| ((x * width as u64 / end) as usize).min(width - 1);
+
+        let mut s = String::new();
+        let _r = writeln!(s, "{:>8} {:>8} {:<ew$} {:<uw$} 0ms{:>w$}", "offset", "timeout", "engine", "url", format!("{}ms", end), ew = engine_width, uw = url_width, w = width.saturating_sub(1));
+        for node in self.iter() {
+            let start = column(node.offset);
+            let (stop, fill) = match node.timeout {
+                Some(t) => (column(node.offset + t.as_millis() as u64), '='),
+                None => (width - 1, '-'),
+            };
+            let bar: String = (0..width).map(|i| {
+                match i {
+                    x if x == start => '|',
+                    x if x > start && x <= stop => fill,
+                    _ => ' ',
+                }
+            }).collect();
+            let timeout = node.timeout.map_or("-".to_string(), |t| t.as_millis().to_string());
+            let _r = writeln!(s, "{:>8} {:>8} {:<ew$} {:<uw$} [{}]", node.offset, timeout, node.engine, node.url.as_str(), bar, ew = engine_width, uw = url_width);
+        }
+        s
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use url::Url;
+
+    use crate::control::graph::{
+        ControllerGraph,
+        GraphNode,
+    };
+
+    fn graph() -> ControllerGraph {
+        let mut g = ControllerGraph::new();
+        let mut node = GraphNode::new(0, &"foo".to_string(), Url::parse("http://one.foo.com/deadbeef").unwrap());
+        node.timeout = Some(Duration::from_millis(100));
+        node.digest = vec![0xde, 0xad, 0xbe, 0xef];
+        node.source = Some(0);
+        node.endpoint = Some(0);
+        g.add_node(node);
+        g.add(100, &"bar".to_string(), "http://only.bar.com/beeffeed".to_string());
+        g
+    }
+
+    #[test]
+    fn test_export_dot() {
+        let s = graph().to_dot();
+        assert!(s.starts_with("digraph fadfada {\n"));
+        assert!(s.contains("\tsubgraph cluster_0 {\n\t\tlabel=\"foo\";\n\t\tn0 [label=\"+0ms\\nhttp://one.foo.com/deadbeef\"];\n\t}\n"));
+        assert!(s.contains("\tsubgraph cluster_1 {\n\t\tlabel=\"bar\";\n\t\tn1 [label=\"+100ms\\nhttp://only.bar.com/beeffeed\"];\n\t}\n"));
+        assert!(s.ends_with("\tn0 -> n1;\n}\n"));
+    }
+
+    #[test]
+    fn test_export_timeline() {
+        let s = graph().to_timeline(10);
+        let lines: Vec<&str> = s.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].ends_with("0ms    100ms"));
+        assert!(lines[1].ends_with("[|=========]"));
+        assert!(lines[2].ends_with("[         |]"));
+        assert!(lines[2].contains("     100        - bar"));
+    }
+}
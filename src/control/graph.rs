@@ -1,111 +1,345 @@
 use std::fmt;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+use std::collections::btree_map;
+use std::iter::Flatten;
+use std::ops::Index;
+use std::time::Duration;
 
 use log::debug;
+use url::Url;
 
-use crate::endpoint::Endpoint;
+use crate::error::Error;
+use crate::keystore::Fingerprint;
 use crate::source::Engine;
+use crate::resolver::{
+    Digest,
+    ResolverError,
+    Signature,
+};
+use crate::validator::{
+    Validator,
+    NOOPVALIDATOR,
+};
+
+/// A source left out of a [ControllerGraph], with the reason why.
+#[derive(Debug, Clone)]
+pub struct Skipped {
+    pub engine: Engine,
+    pub error: ResolverError,
+}
+
+/// How requests scheduled at the same offset in a [ControllerGraph] are spaced.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stagger {
+    /// Requests at the same offset are kept there, in the order they were added.
+    None,
+    /// A request scheduled at an offset that is already taken is moved forward by the given
+    /// number of milliseconds until it reaches a free offset.
+    Spacing(u64),
+}
+
+/// A single request in a [ControllerGraph].
+///
+/// Nodes generated by a [Controller](super::Controller) carry everything needed to dispatch and
+/// validate the request, so executors do not need to look up the controller again.
+#[derive(Clone)]
+pub struct GraphNode {
+    /// Offset in milliseconds from the start of the graph at which the request is made.
+    pub offset: u64,
+    /// The URL of the resource at the endpoint.
+    pub url: Url,
+    pub engine: Engine,
+    /// The time after which the request should be abandoned, if any.
+    pub timeout: Option<Duration>,
+    /// The [Validator] of the endpoint.
+    pub validator: &'static dyn Validator,
+    /// The keys of the source that signatures are accepted from.
+    pub trusted_keys: Vec<Fingerprint>,
+    /// The digest of the resource, as resolved for the engine.
+    pub digest: Digest,
+    /// The signature of the resource, if the resolver has one.
+    pub signature: Option<Signature>,
+    /// Index of the source in the controller the node was generated from.
+    pub source: Option<usize>,
+    /// Index of the endpoint in the source the node was generated from.
+    pub endpoint: Option<usize>,
+}
+
+impl GraphNode {
+    /// Create a node that is not validated and does not time out.
+    pub fn new(offset: u64, engine: &Engine, url: Url) -> GraphNode {
+        GraphNode {
+            offset,
+            url,
+            engine: engine.clone(),
+            timeout: None,
+            validator: &NOOPVALIDATOR,
+            trusted_keys: vec![],
+            digest: vec![],
+            signature: None,
+            source: None,
+            endpoint: None,
+        }
+    }
+}
+
+impl fmt::Debug for GraphNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("GraphNode")
+            .field("offset", &self.offset)
+            .field("url", &self.url.as_str())
+            .field("engine", &self.engine)
+            .field("timeout", &self.timeout)
+            .field("digest", &hex::encode(&self.digest))
+            .field("source", &self.source)
+            .field("endpoint", &self.endpoint)
+            .finish()
+    }
+}
 
 /// Represents the sequence and timings of a single resource request as described by the
 /// [super:control.Controller] state at the time of request.
+///
+/// Requests are ordered by offset, and requests at the same offset in the order they were added.
+///
+/// For review and debugging, the graph can be rendered with [ControllerGraph::to_dot] and
+/// [ControllerGraph::to_timeline], and with the `json` feature as JSON with `to_json`.
+#[derive(Clone)]
 pub struct ControllerGraph {
-    v: HashMap<u64, (String, Engine)>,
+    v: BTreeMap<u64, Vec<GraphNode>>,
     l: usize,
-    it: Vec<u64>,
-    it_active: bool,
+    skipped: Vec<Skipped>,
+    stagger: Stagger,
+}
+
+impl Default for ControllerGraph {
+    fn default() -> ControllerGraph {
+        ControllerGraph::new()
+    }
 }
 
 impl ControllerGraph {
     pub fn new() -> ControllerGraph {
+        ControllerGraph::with_stagger(Stagger::None)
+    }
+
+    /// Create an empty graph spacing colliding requests with the given [Stagger] policy.
+    pub fn with_stagger(stagger: Stagger) -> ControllerGraph {
         ControllerGraph{
-            v: HashMap::new(),
-            it: Vec::<u64>::new(),
-            it_active: false,
+            v: BTreeMap::new(),
             l: 0,
+            skipped: vec![],
+            stagger,
         }
     }
 
     /// Add a new offset/url pair to the graph.
-    //pub fn add(&mut self, d: u64, e: String) {
+    ///
+    /// Panics if the url cannot be parsed; see [ControllerGraph::try_add].
     pub fn add(&mut self, d: u64, engine: &Engine, pointer_url: String) { 
-        let offset = self.find_next_offset(d);
+        self.try_add(d, engine, pointer_url).unwrap();
+    }
+
+    /// Add a new offset/url pair to the graph.
+    ///
+    /// Will error with [Error::InvalidUrl] if the url cannot be parsed.
+    pub fn try_add(&mut self, d: u64, engine: &Engine, pointer_url: String) -> Result<(), Error> {
+        let url = match Url::parse(&pointer_url) {
+            Ok(v) => v,
+            Err(e) => {
+                return Err(Error::InvalidUrl(pointer_url, e));
+            },
+        };
+        self.add_node(GraphNode::new(d, engine, url));
+        Ok(())
+    }
+
+    /// Add a [GraphNode] to the graph.
+    ///
+    /// The offset of the node is changed if it collides with another node under the [Stagger]
+    /// policy of the graph.
+    pub fn add_node(&mut self, mut node: GraphNode) {
+        let offset = self.find_next_offset(node.offset);
        
-        debug!("using offset {} (requested {}) for {}", offset, d, pointer_url);
-        self.v.insert(offset, (pointer_url, engine.clone()));
+        debug!("using offset {} (requested {}) for {}", offset, node.offset, node.url);
+        node.offset = offset;
+        self.v.entry(offset).or_default().push(node);
         self.l += 1;
     }
 
+    /// Record a source that was left out of the graph.
+    pub fn add_skipped(&mut self, engine: &Engine, error: ResolverError) {
+        debug!("skipping engine {}: {}", engine, error);
+        self.skipped.push(Skipped {
+            engine: engine.clone(),
+            error,
+        });
+    }
+
+    /// The sources that were left out of the graph, in the order they were processed.
+    pub fn skipped(&self) -> &[Skipped] {
+        &self.skipped
+    }
+
     pub fn len(&self) -> usize {
         self.l
     }
 
-    pub fn keys(&mut self) -> Vec<u64> {
-        self.fill_it();
-        self.it.clone()
+    pub fn is_empty(&self) -> bool {
+        self.l == 0
+    }
+
+    /// The offsets of all requests in the graph, in order.
+    pub fn keys(&self) -> Vec<u64> {
+        self.iter().map(|v| v.offset).collect()
+    }
+
+    /// The request at the given position in the graph, if any.
+    pub fn get(&self, i: usize) -> Option<&GraphNode> {
+        self.iter().nth(i)
     }
 
-    pub fn get(&self, i: usize) -> (u64, String, Engine) {
-        let k = self.it[i];
-        let v = &self.v[&k];
-        (k, v.0.clone(), v.1.clone())
+    /// Iterate the nodes of the graph in order, without consuming it.
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            inner: self.v.values().flatten(),
+        }
     }
 
+    /// The offset a request scheduled at the given offset will be added at.
+    ///
+    /// This is the offset itself, unless a [Stagger] policy is set and the offset is taken.
     pub fn find_next_offset(&self, offset_default: u64) -> u64 {
+        let spacing = match self.stagger {
+            Stagger::None => {
+                return offset_default;
+            },
+            Stagger::Spacing(x) => x.max(1),
+        };
         let mut offset = offset_default;
-        loop {
-            match self.v.get(&offset) {
-                Some(_) => {
-                    offset += 1;
-                },
-                None => {
-                    return offset;
-                }
-            }
+        while self.v.contains_key(&offset) {
+            offset += spacing;
         }
+        offset
     }
-    
-    fn fill_it(&mut self) {
-        self.it = Vec::<u64>::new();
-        for k in self.v.keys() {
-            self.it.push(*k);
-        }
-        self.it.sort();
-        self.it.reverse();
+}
+
+/// Iterator over the nodes of a [ControllerGraph], in order.
+pub struct Iter<'a> {
+    inner: Flatten<btree_map::Values<'a, u64, Vec<GraphNode>>>,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = &'a GraphNode;
+
+    fn next(&mut self) -> Option<&'a GraphNode> {
+        self.inner.next()
+    }
+}
+
+/// Iterator over the nodes of a consumed [ControllerGraph], in order.
+pub struct IntoIter {
+    inner: Flatten<btree_map::IntoValues<u64, Vec<GraphNode>>>,
+}
+
+impl Iterator for IntoIter {
+    type Item = GraphNode;
+
+    fn next(&mut self) -> Option<GraphNode> {
+        self.inner.next()
+    }
+}
+
+impl<'a> IntoIterator for &'a ControllerGraph {
+    type Item = &'a GraphNode;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
     }
 }
 
-impl Iterator for ControllerGraph {
-    type Item = (u64, String, Engine);
+impl IntoIterator for ControllerGraph {
+    type Item = GraphNode;
+    type IntoIter = IntoIter;
 
-    fn next(&mut self) -> Option<(u64, String, Engine)> {
-        if !self.it_active {
-            self.fill_it();
-            self.it_active = true;
+    fn into_iter(self) -> IntoIter {
+        IntoIter {
+            inner: self.v.into_values().flatten(),
         }
-        match self.it.pop() {
-            Some(i) => {
-                let s = self.v.get(&i).unwrap();
-                Some((i, s.0.clone(), s.1.clone()))
-            },
-            None => {
-                self.it_active = false;
-                None
-            },
+    }
+}
+
+impl Index<usize> for ControllerGraph {
+    type Output = GraphNode;
+
+    fn index(&self, i: usize) -> &GraphNode {
+        match self.get(i) {
+            Some(v) => v,
+            None => panic!("graph index {} out of range for {} nodes", i, self.l),
         }
     }
 }
 
+/// Renders the graph as a timeline with one request per line, e.g. `+200ms foo http://foo.com/deadbeef`.
 impl fmt::Display for ControllerGraph {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { //Result<(), fmt::Error> {
-        // consider tradeoffs against BtreeMap; which is faster for a single sort?
-        self.it.iter().for_each(|v| {
-               match Some(v) {
-                   Some(k) => {
-                       let _r = fmt::write(f, format_args!("{} {:?}\n", k, self.v.get(k))); 
-                   },
-                   None => {},
-               }
-        });
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for v in self.iter() {
+            writeln!(f, "+{}ms {} {}", v.offset, v.engine, v.url)?;
+        }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        ControllerGraph,
+        GraphNode,
+        Stagger,
+    };
+    use crate::error::Error;
+
+    #[test]
+    fn test_graph_try_add() {
+        let foo = "foo".to_string();
+        let mut g = ControllerGraph::new();
+        assert!(g.try_add(0, &foo, "mock://one".to_string()).is_ok());
+        let r = g.try_add(100, &foo, "not a url".to_string());
+        assert!(matches!(r, Err(Error::InvalidUrl(v, _)) if v == "not a url"));
+        assert_eq!(g.len(), 1);
+    }
+
+    #[test]
+    fn test_graph_order() {
+        let foo = "foo".to_string();
+        let bar = "bar".to_string();
+        let mut g = ControllerGraph::new();
+        g.add(100, &foo, "mock://two".to_string());
+        g.add(0, &bar, "mock://one".to_string());
+        g.add(100, &bar, "mock://three".to_string());
+        assert_eq!(g.len(), 3);
+        assert_eq!(g.keys(), vec![0, 100, 100]);
+        assert_eq!(g[1].url.as_str(), "mock://two");
+        assert_eq!(g[1].engine, foo);
+        assert_eq!(g.get(2).unwrap().url.as_str(), "mock://three");
+        assert!(g.get(3).is_none());
+
+        let urls: Vec<&str> = g.iter().map(|v| v.url.as_str()).collect();
+        assert_eq!(urls, vec!["mock://one", "mock://two", "mock://three"]);
+        let mut offsets = vec![];
+        for v in &g {
+            offsets.push(v.offset);
+        }
+        assert_eq!(offsets, vec![0, 100, 100]);
+        assert_eq!(format!("{}", g), "+0ms bar mock://one\n+100ms foo mock://two\n+100ms bar mock://three\n");
+        let nodes: Vec<GraphNode> = g.into_iter().collect();
+        assert_eq!(nodes.len(), 3);
+
+        let mut g = ControllerGraph::with_stagger(Stagger::Spacing(10));
+        g.add(100, &foo, "mock://two".to_string());
+        g.add(100, &bar, "mock://three".to_string());
+        g.add(110, &foo, "mock://four".to_string());
+        assert_eq!(g.keys(), vec![100, 110, 120]);
+    }
+}
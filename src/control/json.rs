@@ -0,0 +1,213 @@
+use std::time::Duration;
+
+use serde::Serialize;
+use serde_json::Value;
+use url::Url;
+
+use crate::error::Error;
+
+use super::graph::{
+    ControllerGraph,
+    GraphNode,
+};
+
+#[derive(Serialize)]
+struct GraphJson<'g> {
+    nodes: Vec<NodeJson<'g>>,
+}
+
+/// The JSON representation of a [GraphNode], with times in milliseconds and binary values as
+/// hex strings.
+#[derive(Serialize)]
+struct NodeJson<'g> {
+    offset: u64,
+    engine: &'g str,
+    url: &'g str,
+    timeout: Option<u64>,
+    digest: String,
+    signature: Option<String>,
+    trusted_keys: Vec<String>,
+    source: Option<usize>,
+    endpoint: Option<usize>,
+}
+
+fn node_json(node: &GraphNode) -> NodeJson<'_> {
+    NodeJson {
+        offset: node.offset,
+        engine: &node.engine,
+        url: node.url.as_str(),
+        timeout: node.timeout.map(|v| v.as_millis() as u64),
+        digest: hex::encode(&node.digest),
+        signature: node.signature.as_ref().map(hex::encode),
+        trusted_keys: node.trusted_keys.iter().map(hex::encode).collect(),
+        source: node.source,
+        endpoint: node.endpoint,
+    }
+}
+
+fn node_from_json(v: &Value, i: usize) -> Result<GraphNode, Error> {
+    let schema = |k: &str, what: &str| Error::Schema(format!("nodes[{}].{} must be {}", i, k, what));
+    let optional = |k: &str| v.get(k).filter(|x| !x.is_null());
+    let number = |k: &str| -> Result<Option<u64>, Error> {
+        match optional(k) {
+            Some(x) => x.as_u64().map(Some).ok_or_else(|| schema(k, "a non-negative integer")),
+            None => Ok(None),
+        }
+    };
+    let string = |k: &str| -> Result<Option<&str>, Error> {
+        match optional(k) {
+            Some(x) => x.as_str().map(Some).ok_or_else(|| schema(k, "a string")),
+            None => Ok(None),
+        }
+    };
+    let bytes = |k: &str, x: &str| {
+        hex::decode(x).map_err(|e| Error::Decode(format!("nodes[{}].{}: {}", i, k, e)))
+    };
+
+    let offset = number("offset")?.ok_or_else(|| schema("offset", "set"))?;
+    let engine = string("engine")?.ok_or_else(|| schema("engine", "set"))?.to_string();
+    let url_src = string("url")?.ok_or_else(|| schema("url", "set"))?;
+    let url = Url::parse(url_src).map_err(|e| Error::InvalidUrl(url_src.to_string(), e))?;
+
+    let mut node = GraphNode::new(offset, &engine, url);
+    node.timeout = number("timeout")?.map(Duration::from_millis);
+    if let Some(x) = string("digest")? {
+        node.digest = bytes("digest", x)?;
+    }
+    if let Some(x) = string("signature")? {
+        node.signature = Some(bytes("signature", x)?);
+    }
+    if let Some(x) = optional("trusted_keys") {
+        let keys = x.as_array().ok_or_else(|| schema("trusted_keys", "a list"))?;
+        for k in keys {
+            let k = k.as_str().ok_or_else(|| schema("trusted_keys", "a list of strings"))?;
+            node.trusted_keys.push(bytes("trusted_keys", k)?);
+        }
+    }
+    node.source = number("source")?.map(|x| x as usize);
+    node.endpoint = number("endpoint")?.map(|x| x as usize);
+    Ok(node)
+}
+
+impl ControllerGraph {
+    /// Render the graph as a JSON object, with the nodes in order under `nodes`.
+    ///
+    /// Times are in milliseconds, and binary values are hex strings. Validators cannot be
+    /// represented, and are left out.
+    pub fn to_json(&self) -> String {
+        let v = GraphJson {
+            nodes: self.iter().map(node_json).collect(),
+        };
+        serde_json::to_string(&v).unwrap()
+    }
+
+    /// Load a graph from JSON as rendered by [ControllerGraph::to_json].
+    ///
+    /// Only `offset`, `engine` and `url` are required for each node. Since validators are not
+    /// part of the JSON, the nodes use the [NoopValidator](crate::validator::NoopValidator).
+    /// Nodes keep their offsets even if they collide.
+    ///
+    /// Since the JSON may come from the network, documents nested more than 128 levels deep are
+    /// rejected with [Error::Decode] instead of being parsed recursively.
+    pub fn from_json(s: &str) -> Result<ControllerGraph, Error> {
+        let v: Value = serde_json::from_str(s).map_err(|e| Error::Decode(e.to_string()))?;
+        let nodes = v.get("nodes")
+            .and_then(|x| x.as_array())
+            .ok_or_else(|| Error::Schema("nodes must be a list".to_string()))?;
+        let mut g = ControllerGraph::new();
+        for (i, node) in nodes.iter().enumerate() {
+            g.add_node(node_from_json(node, i)?);
+        }
+        Ok(g)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use url::Url;
+
+    use crate::adapter::Registry;
+    use crate::control::graph::{
+        ControllerGraph,
+        GraphNode,
+    };
+    use crate::error::Error;
+    use crate::exec::{
+        Request,
+        RequestError,
+        Runner,
+    };
+    use crate::timing::Scheduler;
+
+    fn graph() -> ControllerGraph {
+        let mut g = ControllerGraph::new();
+        let mut node = GraphNode::new(0, &"foo".to_string(), Url::parse("http://one.foo.com/deadbeef").unwrap());
+        node.timeout = Some(Duration::from_millis(100));
+        node.digest = vec![0xde, 0xad, 0xbe, 0xef];
+        node.source = Some(0);
+        node.endpoint = Some(0);
+        g.add_node(node);
+        g.add(100, &"bar".to_string(), "http://only.bar.com/beeffeed".to_string());
+        g
+    }
+
+    #[test]
+    fn test_export_json() {
+        assert_eq!(graph().to_json(), "{\"nodes\":[\
+{\"offset\":0,\"engine\":\"foo\",\"url\":\"http://one.foo.com/deadbeef\",\"timeout\":100,\"digest\":\"deadbeef\",\"signature\":null,\"trusted_keys\":[],\"source\":0,\"endpoint\":0},\
+{\"offset\":100,\"engine\":\"bar\",\"url\":\"http://only.bar.com/beeffeed\",\"timeout\":null,\"digest\":\"\",\"signature\":null,\"trusted_keys\":[],\"source\":null,\"endpoint\":null}\
+]}");
+    }
+
+    #[test]
+    fn test_import_json() {
+        let mut g = graph();
+        let mut node = GraphNode::new(100, &"baz".to_string(), Url::parse("file:///tmp/baz").unwrap());
+        node.signature = Some(vec![1, 2]);
+        node.trusted_keys = vec![vec![3], vec![4, 5]];
+        g.add_node(node);
+        let s = g.to_json();
+
+        let imported = ControllerGraph::from_json(&s).unwrap();
+        assert_eq!(imported.len(), 3);
+        assert_eq!(imported[0].timeout, Some(Duration::from_millis(100)));
+        assert_eq!(imported[0].digest, vec![0xde, 0xad, 0xbe, 0xef]);
+        assert_eq!(imported[2].trusted_keys, vec![vec![3], vec![4, 5]]);
+        assert_eq!(imported.to_json(), s);
+
+        let g = ControllerGraph::from_json("{\"nodes\":[{\"offset\":5,\"engine\":\"foo\",\"url\":\"mock://one\"}]}").unwrap();
+        assert_eq!(g.keys(), vec![5]);
+        assert_eq!(g[0].timeout, None);
+
+        assert!(matches!(ControllerGraph::from_json("{\"nodes\":"), Err(Error::Decode(_))));
+        let s = format!("{{\"nodes\":{}", "[".repeat(200000));
+        assert!(matches!(ControllerGraph::from_json(&s), Err(Error::Decode(v)) if v.contains("recursion limit")));
+        assert!(matches!(ControllerGraph::from_json("[]"), Err(Error::Schema(_))));
+        let r = ControllerGraph::from_json("{\"nodes\":[{\"offset\":-5,\"engine\":\"foo\",\"url\":\"mock://one\"}]}");
+        assert!(matches!(r, Err(Error::Schema(v)) if v == "nodes[0].offset must be a non-negative integer"));
+        let r = ControllerGraph::from_json("{\"nodes\":[{\"offset\":0,\"engine\":\"foo\",\"url\":\"one\"}]}");
+        assert!(matches!(r, Err(Error::InvalidUrl(_, _))));
+    }
+
+    #[test]
+    fn test_import_replay() {
+        let s = graph().to_json();
+        let mut adapters = Registry::new();
+        adapters.add_scheme("http", Arc::new(|request: &Request| {
+            match request.url.as_str() {
+                "http://only.bar.com/beeffeed" => Ok(vec![1, 2, 3]),
+                _ => Err(RequestError::NotFound),
+            }
+        }));
+        let runner = Runner::new(&Scheduler {
+            delay: 0,
+            timeout: 0,
+        });
+        let r = runner.run(ControllerGraph::from_json(&s).unwrap(), &adapters).unwrap();
+        assert_eq!(r.engine, "bar");
+        assert_eq!(r.offset, 100);
+    }
+}
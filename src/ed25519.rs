@@ -0,0 +1,102 @@
+use std::convert::TryFrom;
+
+use log::debug;
+
+use ed25519_dalek::{
+    Signature as Ed25519Signature,
+    VerifyingKey,
+};
+
+use crate::keystore::{
+    Key,
+    Keystore,
+};
+use crate::resolver::{
+    Digest,
+    Signature,
+};
+use crate::validator::{
+    SignatureValidator,
+    SignatureVerifier,
+    Validator,
+};
+
+/// Verifies detached ed25519 signatures over the content digest.
+///
+/// Public keys are the 32 byte compressed point, and signatures are 64 bytes.
+pub struct Ed25519Verifier {
+}
+
+impl SignatureVerifier for Ed25519Verifier {
+    fn verify_signature(&self, key: &Key, digest: &Digest, signature: &Signature) -> bool {
+        let key = match VerifyingKey::try_from(key.as_slice()) {
+            Ok(v) => v,
+            Err(e) => {
+                debug!("invalid ed25519 key {}: {}", hex::encode(key), e);
+                return false;
+            },
+        };
+        let signature = match Ed25519Signature::from_slice(signature) {
+            Ok(v) => v,
+            Err(e) => {
+                debug!("invalid ed25519 signature: {}", e);
+                return false;
+            },
+        };
+        key.verify_strict(digest, &signature).is_ok()
+    }
+}
+
+pub const ED25519VERIFIER: Ed25519Verifier = Ed25519Verifier{};
+
+/// Create a [SignatureValidator] accepting ed25519 signatures by keys in the [Keystore].
+///
+/// The signature only covers the digest, so the `content` validator must check that the content
+/// matches the digest.
+pub fn validator<'a>(keystore: &'a dyn Keystore, content: &'a dyn Validator) -> SignatureValidator<'a> {
+    SignatureValidator::new(keystore, &ED25519VERIFIER, content)
+}
+
+#[cfg(test)]
+mod tests {
+    use ed25519_dalek::{
+        Signer,
+        SigningKey,
+    };
+
+    use super::validator;
+    use crate::keystore::{
+        Keystore,
+        MemoryKeystore,
+    };
+    use crate::mock::TestValidator;
+    use crate::validator::Validator;
+
+    #[test]
+    fn test_ed25519_validator() {
+        let signer_foo = SigningKey::from_bytes(&[1; 32]);
+        let signer_bar = SigningKey::from_bytes(&[2; 32]);
+        let mut keystore = MemoryKeystore::new();
+        let fp_foo = keystore.add(signer_foo.verifying_key().to_bytes().to_vec());
+        let fp_bar = keystore.add(signer_bar.verifying_key().to_bytes().to_vec());
+
+        let digest: Vec<u8> = vec![1, 2, 3];
+        let signature = signer_foo.sign(&digest).to_bytes().to_vec();
+        let content = TestValidator{};
+        let mut v = validator(&keystore, &content);
+
+        let trusted_foo = [fp_foo];
+        assert!(v.verify_trusted(&digest, Some(&digest), Some(&signature), &trusted_foo));
+        assert!(!v.verify_trusted(&digest, Some(&digest), Some(&signature), &[fp_bar]));
+        assert!(!v.verify_trusted(&digest, Some(&vec![4, 5, 6]), Some(&signature), &trusted_foo));
+        assert!(!v.verify_trusted(&digest, None, Some(&signature), &trusted_foo));
+
+        assert!(!v.verify(&digest, Some(&digest), Some(&signature)));
+        v.any_key = true;
+        assert!(v.verify(&digest, Some(&digest), Some(&signature)));
+        assert!(!v.verify(&digest, Some(&digest), None));
+        let other = vec![1, 2, 4];
+        assert!(!v.verify(&other, Some(&other), Some(&signature)));
+        assert!(!v.verify(&digest, Some(&digest), Some(&signature[1..].to_vec())));
+    }
+}
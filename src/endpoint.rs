@@ -2,6 +2,7 @@ use std::{
     fmt,
     path,
 };
+use crate::error::Error;
 use crate::validator::{
     Validator,
     NOOPVALIDATOR,
@@ -15,46 +16,200 @@ use url::Url;
 /// An endpoint also includes a [Validator], which will verify that content retrieved from the
 /// endpoint is valid.
 pub struct Endpoint<'a> {
-    /// Endpoint url
+    /// Endpoint url. If the endpoint has a [Template], this is the part of the template before
+    /// the first placeholder.
     pub url: Url,
     /// Content validator for content returned from the endpoint. Enabling endpoint-specific
     /// validation allows for different signatories for different locations.
     pub validator: &'a (dyn Validator + 'a),
+    /// Template the pointer is substituted into, instead of being appended to the url.
+    pub template: Option<Template>,
+}
+
+enum Part {
+    Text(String),
+    Pointer(usize, Option<usize>),
+}
+
+/// A URL template with placeholders for the pointer of a resource.
+///
+/// The placeholder `{pointer}` is replaced with the whole pointer, and `{pointer[a:b]}` with the
+/// characters from `a` up to `b`, e.g. `{pointer[0:2]}` for the first two. Either bound may be
+/// left out. Placeholders are only allowed after the host and port, as in
+/// `https://mirror.example/{pointer[0:2]}/{pointer}`.
+///
+/// Variables like `{region}` and environment variables like `${HOST}` are substituted by the
+/// yaml `Loader` before the template is parsed. Anywhere else, including the `FromYaml` and
+/// `serde` implementations, they are an error.
+pub struct Template {
+    src: String,
+    parts: Vec<Part>,
+}
+
+impl Template {
+    /// Parse a URL template.
+    ///
+    /// Will error with [Error::InvalidTemplate] if a placeholder is not one of the above, or is a
+    /// variable.
+    pub fn parse(src: &str) -> Result<Template, Error> {
+        let invalid = |reason: String| Error::InvalidTemplate(src.to_string(), reason);
+        let mut parts = vec![];
+        let mut rest = src;
+        while let Some(i) = rest.find('{') {
+            if i > 0 {
+                parts.push(Part::Text(rest[..i].to_string()));
+            }
+            let j = match rest[i..].find('}') {
+                Some(v) => i + v,
+                None => {
+                    return Err(invalid("unterminated placeholder".to_string()));
+                },
+            };
+            let name = &rest[i + 1..j];
+            let range = match name.strip_prefix("pointer") {
+                Some("") => Some((0, None)),
+                Some(v) => v.strip_prefix('[').and_then(|v| v.strip_suffix(']')).and_then(|v| {
+                    let (a, b) = v.split_once(':')?;
+                    let a = match a {
+                        "" => 0,
+                        _ => a.parse().ok()?,
+                    };
+                    let b = match b {
+                        "" => None,
+                        _ => Some(b.parse().ok()?),
+                    };
+                    Some((a, b))
+                }),
+                None => None,
+            };
+            match range {
+                Some((a, b)) => parts.push(Part::Pointer(a, b)),
+                None if rest[..i].ends_with('$') => {
+                    return Err(invalid(format!("environment variable '${{{}}}' requires the yaml Loader", name)));
+                },
+                None if !name.starts_with("pointer") => {
+                    return Err(invalid(format!("variable '{{{}}}' requires the yaml Loader", name)));
+                },
+                None => {
+                    return Err(invalid(format!("unknown placeholder '{{{}}}'", name)));
+                },
+            };
+            rest = &rest[j + 1..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Text(rest.to_string()));
+        }
+        Ok(Template {
+            src: src.to_string(),
+            parts,
+        })
+    }
+
+    /// Substitute the pointer into the template.
+    pub fn render(&self, pointer: &str) -> String {
+        let mut s = String::new();
+        for part in self.parts.iter() {
+            match part {
+                Part::Text(v) => s.push_str(v),
+                Part::Pointer(a, b) => {
+                    let n = b.unwrap_or(usize::MAX).saturating_sub(*a);
+                    s.extend(pointer.chars().skip(*a).take(n));
+                },
+            };
+        }
+        s
+    }
+}
+
+impl fmt::Display for Template {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.src)
+    }
 }
 
 impl<'a> Endpoint<'a> {
-    pub fn new(endpoint_url_src: &str, _validator: Option<&dyn Validator>) -> Endpoint<'a> {
-        let endpoint_url = Url::parse(endpoint_url_src).unwrap();
-        Endpoint{
+    /// Create a new endpoint from a URL string.
+    ///
+    /// If no [Validator] is given, the [NoopValidator](crate::validator::NoopValidator) is used.
+    ///
+    /// Panics if the URL cannot be parsed; see [Endpoint::try_new].
+    pub fn new(endpoint_url_src: &str, validator: Option<&'a dyn Validator>) -> Endpoint<'a> {
+        Endpoint::try_new(endpoint_url_src, validator).unwrap()
+    }
+
+    /// Create a new endpoint from a URL string.
+    ///
+    /// If the string contains placeholders, it is parsed as a [Template].
+    ///
+    /// Will error if the URL cannot be parsed.
+    pub fn try_new(endpoint_url_src: &str, validator: Option<&'a dyn Validator>) -> Result<Endpoint<'a>, Error> {
+        let (base, template) = match endpoint_url_src.find('{') {
+            Some(i) => (&endpoint_url_src[..i], Some(Template::parse(endpoint_url_src)?)),
+            None => (endpoint_url_src, None),
+        };
+        let endpoint_url = match Url::parse(base) {
+            Ok(v) => v,
+            Err(e) => {
+                return Err(Error::InvalidUrl(endpoint_url_src.to_string(), e));
+            },
+        };
+        if template.is_some() {
+            // the host and port must be literal, so that every pointer renders to a valid url
+            let literal_authority = match base.find("://") {
+                Some(i) => base[i + 3..].contains(['/', '?', '#']),
+                None => true,
+            };
+            if !literal_authority {
+                let reason = "placeholders must follow the host".to_string();
+                return Err(Error::InvalidTemplate(endpoint_url_src.to_string(), reason));
+            }
+        }
+        Ok(Endpoint{
             url: endpoint_url,
-            validator: &NOOPVALIDATOR,
-        }        
+            validator: validator.unwrap_or(&NOOPVALIDATOR),
+            template,
+        })
     }
 
     /// Calculates the URL of a resource in the context of the specific endpoint.
     ///
-    /// The endpoint will typically be the string representation of a digest.
+    /// The pointer will typically be the string representation of a digest. It is appended to
+    /// the path of the url, unless the endpoint has a [Template].
     ///
     /// TODO: pointer should probably be of [Digest](crate::resolver::Digest), or a dedicated type for reference,
     /// TODO: enforce zero port for schemes that do not have ports associated with them (file)
     pub fn url_for(&self, pointer: &str) -> String {
+        self.resolve(pointer).to_string()
+    }
+
+    /// Same as [Endpoint::url_for], returning the parsed [Url].
+    pub fn resolve(&self, pointer: &str) -> Url {
+        if let Some(v) = &self.template {
+            // the host and port are literal, and anything after them parses
+            return Url::parse(&v.render(pointer)).unwrap();
+        }
         let mut pointer_url = self.url.clone();
         let new_path = path::Path::new(self.url.path())
             .join(pointer);
         pointer_url.set_path(new_path.to_str().unwrap());
-        return pointer_url.to_string();
+        pointer_url
     }
 }
 
 impl<'a> fmt::Display for Endpoint<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-          fmt::write(f, format_args!("{}", self.url.to_string()))
+        match &self.template {
+            Some(v) => write!(f, "{}", v),
+            None => fmt::write(f, format_args!("{}", self.url)),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::Endpoint;
+    use crate::error::Error;
+    use crate::mock::TestValidator;
 
     #[test]
     fn test_endpoint_create() {
@@ -63,6 +218,12 @@ mod tests {
         assert_eq!(format!("{}", e), "https://localhost:8521/foo");
     }
 
+    #[test]
+    fn test_endpoint_invalid() {
+        let r = Endpoint::try_new("foo.com/bar", None);
+        assert!(matches!(r, Err(Error::InvalidUrl(v, _)) if v == "foo.com/bar"));
+    }
+
     #[test]
     fn test_endpoint_pointer() {
         let orig_url = "https://localhost:8521/foo";
@@ -79,4 +240,49 @@ mod tests {
         assert_eq!(format!("{}", endpoint_url), "file:///tmp/foobar/deadbeef");
     }
 
+    #[test]
+    fn test_endpoint_template() {
+        let e: Endpoint = Endpoint::new("https://mirror.example/{pointer[0:2]}/{pointer[2:4]}/{pointer}?x={pointer[:2]}", None);
+        assert_eq!(e.url.as_str(), "https://mirror.example/");
+        assert_eq!(e.url_for("deadbeef"), "https://mirror.example/de/ad/deadbeef?x=de");
+        assert_eq!(e.url_for("d"), "https://mirror.example/d//d?x=d");
+        assert_eq!(format!("{}", e), "https://mirror.example/{pointer[0:2]}/{pointer[2:4]}/{pointer}?x={pointer[:2]}");
+
+        let e: Endpoint = Endpoint::new("mock://two/{pointer[4:]}.bin", None);
+        assert_eq!(e.url_for("deadbeef"), "mock://two/beef.bin");
+
+        for (src, reason) in [
+            ("https://{region}.mirror.example/{pointer}", "variable '{region}' requires the yaml Loader"),
+            ("https://${HOST}/{pointer}", "environment variable '${HOST}' requires the yaml Loader"),
+            ("https://mirror.example/{pointer[0]}", "unknown placeholder '{pointer[0]}'"),
+            ("https://mirror.example/{pointerx}", "unknown placeholder '{pointerx}'"),
+            ("https://mirror.example/{pointer", "unterminated placeholder"),
+            ("https://mirror.example{pointer}", "placeholders must follow the host"),
+            ("https://h.example:{pointer[64:]}/x", "placeholders must follow the host"),
+            ("https://h.example:80{pointer}/x", "placeholders must follow the host"),
+        ].iter() {
+            let r = Endpoint::try_new(src, None);
+            assert!(matches!(r, Err(Error::InvalidTemplate(v, e)) if v == *src && e == *reason), "{}", src);
+        }
+        assert!(matches!(Endpoint::try_new("https://{pointer}.ipfs.example", None), Err(Error::InvalidUrl(_, _))));
+
+        let e: Endpoint = Endpoint::new("https://h.example:8080/{pointer[64:]}/x", None);
+        assert_eq!(e.url_for(&"ab".repeat(35)), "https://h.example:8080/ababab/x");
+        let url = e.resolve(&"z:/?#".repeat(17));
+        assert_eq!((url.host_str(), url.port()), (Some("h.example"), Some(8080)));
+    }
+
+    #[test]
+    fn test_endpoint_validator() {
+        let v = TestValidator{};
+        let digest: Vec<u8> = vec![1, 2, 3];
+        let content: Vec<u8> = vec![4, 5, 6];
+
+        let e: Endpoint = Endpoint::new("https://localhost:8521/foo", None);
+        assert!(e.validator.verify(&digest, Some(&content), None));
+
+        let e: Endpoint = Endpoint::new("https://localhost:8521/foo", Some(&v));
+        assert!(e.validator.verify(&digest, Some(&digest), None));
+        assert!(!e.validator.verify(&digest, Some(&content), None));
+    }
 }
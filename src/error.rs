@@ -0,0 +1,104 @@
+use std::error;
+use std::fmt;
+
+use crate::exec::RunError;
+use crate::resolver::{
+    ErrorDetail,
+    ResolverError,
+};
+use crate::source::Engine;
+
+/// Errors returned by the fallible operations of the crate.
+///
+/// Constructors that panic on invalid input, like [Endpoint::new](crate::endpoint::Endpoint::new),
+/// have a `try_` counterpart returning this error instead.
+#[derive(Debug)]
+pub enum Error {
+    /// The string could not be parsed as a URL.
+    InvalidUrl(String, url::ParseError),
+    /// The string is not a valid [Template](crate::endpoint::Template), for the given reason.
+    InvalidTemplate(String, String),
+    /// No resolver entry exists for the [Engine].
+    UnknownEngine(Engine),
+    /// An entry already exists for the [Engine].
+    DuplicateEngine(Engine),
+    /// No resource with the name exists in the [Manifest](crate::manifest::Manifest).
+    UnknownResource(String),
+    /// A value could not be decoded, e.g. a pointer that is not a hex string.
+    Decode(String),
+    /// A document does not have the expected structure.
+    Schema(String),
+    /// Any other error raised by the [Resolver](crate::resolver::Resolver).
+    Resolver(ResolverError),
+    /// No content could be retrieved for a request graph.
+    Fetch(RunError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::InvalidUrl(s, e) => write!(f, "invalid url '{}': {}", s, e),
+            Error::InvalidTemplate(s, e) => write!(f, "invalid url template '{}': {}", s, e),
+            Error::UnknownEngine(e) => write!(f, "unknown engine '{}'", e),
+            Error::DuplicateEngine(e) => write!(f, "duplicate engine '{}'", e),
+            Error::UnknownResource(s) => write!(f, "unknown resource '{}'", s),
+            Error::Decode(s) => write!(f, "decode error: {}", s),
+            Error::Schema(s) => write!(f, "schema error: {}", s),
+            Error::Resolver(e) => write!(f, "{}", e),
+            Error::Fetch(e) => write!(f, "fetch failed: {}", e),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::InvalidUrl(_, e) => Some(e),
+            Error::Resolver(e) => Some(e),
+            Error::Fetch(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<ResolverError> for Error {
+    fn from(e: ResolverError) -> Error {
+        let engine = e.engine().cloned().unwrap_or_default();
+        match e.detail() {
+            ErrorDetail::EngineExistsError => Error::DuplicateEngine(engine),
+            ErrorDetail::UnknownEngineError => Error::UnknownEngine(engine),
+            _ => Error::Resolver(e),
+        }
+    }
+}
+
+impl From<RunError> for Error {
+    fn from(e: RunError) -> Error {
+        Error::Fetch(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as _;
+
+    use super::Error;
+    use crate::resolver::{
+        ErrorDetail,
+        ResolverError,
+    };
+
+    #[test]
+    fn test_error_from_resolver() {
+        let e = Error::from(ResolverError::for_engine(ErrorDetail::UnknownEngineError, "foo".to_string()));
+        assert!(matches!(&e, Error::UnknownEngine(v) if v == "foo"));
+        assert_eq!(e.to_string(), "unknown engine 'foo'");
+
+        let e = Error::from(ResolverError::for_engine(ErrorDetail::EngineExistsError, "bar".to_string()));
+        assert!(matches!(&e, Error::DuplicateEngine(v) if v == "bar"));
+
+        let e = Error::from(ResolverError::new(ErrorDetail::MissingSignatureError));
+        assert!(matches!(&e, Error::Resolver(_)));
+        assert!(e.source().is_some());
+    }
+}
@@ -0,0 +1,896 @@
+use std::fmt;
+use std::collections::HashMap;
+use std::mem;
+use std::panic;
+use std::sync::{
+    Arc,
+    Mutex,
+    mpsc,
+};
+use std::sync::atomic::{
+    AtomicBool,
+    Ordering,
+};
+use std::thread;
+use std::time::{
+    Duration,
+    Instant,
+};
+
+use log::{
+    debug,
+    info,
+};
+
+use crate::adapter::Registry;
+use crate::control::graph::{
+    ControllerGraph,
+    GraphNode,
+};
+use crate::source::Engine;
+use crate::stats::{
+    Outcome,
+    Record,
+    StatsSink,
+};
+use crate::timing::Scheduler;
+
+/// A single request dispatched by the [Runner].
+///
+/// The request is handed to the [Adapter](crate::adapter::Adapter) at the time of its offset in
+/// the [ControllerGraph].
+pub struct Request {
+    /// Offset in milliseconds from the start of the run at which the request was scheduled.
+    pub offset: u64,
+    /// The resolved URL to request the resource from.
+    pub url: String,
+    /// The engine of the [Source](crate::source::Source) the request belongs to.
+    pub engine: Engine,
+    /// The time after which the [Runner] will give up on the request, if any.
+    pub timeout: Option<Duration>,
+    cancel: Arc<AtomicBool>,
+    first_byte: Arc<Mutex<Option<Instant>>>,
+}
+
+impl Request {
+    pub fn new(offset: u64, url: String, engine: Engine) -> Request {
+        Request {
+            offset,
+            url,
+            engine,
+            timeout: None,
+            cancel: Arc::new(AtomicBool::new(false)),
+            first_byte: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Record the time the first byte of content was received.
+    ///
+    /// Adapters should call this when they start receiving content, to enable time-to-first-byte
+    /// measurement in the [Record] of the request. Only the first call has any effect.
+    pub fn mark_first_byte(&self) {
+        let mut first_byte = self.first_byte.lock().unwrap();
+        if first_byte.is_none() {
+            *first_byte = Some(Instant::now());
+        }
+    }
+
+    /// Returns `true` if the result of the request is no longer needed.
+    ///
+    /// This happens when another request has already won the race, or when the request has timed
+    /// out. Fetch implementations that block for a long time should check this regularly and
+    /// abort with [RequestError::Cancelled].
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::SeqCst)
+    }
+}
+
+/// Reasons why a single [Request] did not produce a result.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RequestError {
+    /// The fetch failed, with a description of why.
+    Failed(String),
+    /// No result was returned before the timeout of the request expired.
+    Timeout,
+    /// The request was aborted after noticing it had been cancelled.
+    Cancelled,
+    /// No [Adapter](crate::adapter::Adapter) is registered for the engine or URL scheme.
+    NoAdapter,
+    /// The resource does not exist at the endpoint.
+    NotFound,
+    /// The endpoint refused access to the resource.
+    PermissionDenied,
+    /// The resource exceeds the given size limit in bytes.
+    TooLarge(u64),
+    /// The endpoint responded with an unsuccessful protocol status code, e.g. HTTP 404.
+    Status(u16),
+    /// The content was rejected by the [Validator] of the endpoint.
+    Invalid,
+}
+
+impl fmt::Display for RequestError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RequestError::Failed(s) => write!(f, "request failed: {}", s),
+            RequestError::Timeout => write!(f, "request timed out"),
+            RequestError::Cancelled => write!(f, "request cancelled"),
+            RequestError::NoAdapter => write!(f, "no adapter for request"),
+            RequestError::NotFound => write!(f, "resource not found"),
+            RequestError::PermissionDenied => write!(f, "permission denied"),
+            RequestError::TooLarge(x) => write!(f, "resource larger than {} bytes", x),
+            RequestError::Status(x) => write!(f, "endpoint returned status {}", x),
+            RequestError::Invalid => write!(f, "content failed validation"),
+        }
+    }
+}
+
+impl std::error::Error for RequestError {
+}
+
+/// Records the [RequestError] of a single [Request] that did not succeed.
+#[derive(Debug, Clone)]
+pub struct Failure {
+    pub url: String,
+    pub engine: Engine,
+    pub error: RequestError,
+}
+
+/// Errors returned by [Runner::run] when no result could be retrieved.
+#[derive(Debug)]
+pub enum RunError {
+    /// The [ControllerGraph] did not contain any requests.
+    Empty,
+    /// All requests in the [ControllerGraph] failed or timed out.
+    Exhausted(Vec<Failure>),
+    /// All requests completed without enough matching results to satisfy the [Quorum].
+    ///
+    /// Contains the number of successful results, and the failed requests.
+    NoQuorum(usize, Vec<Failure>),
+}
+
+impl fmt::Display for RunError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RunError::Empty => write!(f, "no requests in graph"),
+            RunError::Exhausted(v) => write!(f, "all {} requests failed", v.len()),
+            RunError::NoQuorum(x, v) => write!(f, "no quorum from {} results ({} failed)", x, v.len()),
+        }
+    }
+}
+
+impl std::error::Error for RunError {
+}
+
+/// The result of the [Request] that won the race.
+#[derive(Debug)]
+pub struct Response {
+    pub offset: u64,
+    pub url: String,
+    pub engine: Engine,
+    pub content: Vec<u8>,
+    /// URLs of all requests that returned the same content, including this one.
+    ///
+    /// Unless a [Quorum] is used, this only contains the URL of the response itself.
+    pub matches: Vec<String>,
+}
+
+/// [Quorum] requires a number of requests to return matching content before a result is
+/// accepted.
+///
+/// Results are grouped by their content. Once `n` requests in the same group have completed,
+/// the group is accepted and remaining requests are cancelled. If `distinct_engines` is set,
+/// the `n` requests must also each come from a different engine.
+#[derive(Debug, Clone)]
+pub struct Quorum {
+    pub n: usize,
+    pub distinct_engines: bool,
+}
+
+impl Quorum {
+    pub fn new(n: usize, distinct_engines: bool) -> Quorum {
+        Quorum {
+            n,
+            distinct_engines,
+        }
+    }
+
+    fn is_met(&self, engines: &[&Engine]) -> bool {
+        let mut count = engines.len();
+        if self.distinct_engines {
+            let mut v = engines.to_vec();
+            v.sort();
+            v.dedup();
+            count = v.len();
+        }
+        count >= self.n
+    }
+}
+
+/// Bookkeeping of a dispatched [Request] that has not yet completed.
+struct Active {
+    deadline: Option<Instant>,
+    started: Instant,
+    cancel: Arc<AtomicBool>,
+    first_byte: Arc<Mutex<Option<Instant>>>,
+}
+
+/// [Runner] executes the requests of a [ControllerGraph].
+///
+/// Each request is dispatched in its own thread at its offset in the graph. The first request
+/// to return content wins, and all other requests are cancelled.
+///
+/// A request that has not returned within the timeout of its [GraphNode] is cancelled and
+/// counted as failed. Nodes without a timeout use the timeout of the [Scheduler] given to the
+/// runner, where `0` means requests never time out.
+///
+/// Content is checked with the [Validator](crate::validator::Validator) of the node. Content
+/// that does not pass validation is discarded, and the race continues with the remaining
+/// requests.
+///
+/// If a [Quorum] is set, the result is only returned once enough requests agree on the
+/// content.
+///
+/// If a [StatsSink] is set, a [Record] is passed to it for every request that was dispatched.
+/// To measure every endpoint in the graph, `complete_remaining` can be set, in which case the
+/// remaining requests are dispatched and completed instead of cancelled after the winner has
+/// been found.
+pub struct Runner {
+    timeout: Option<Duration>,
+    pub quorum: Option<Quorum>,
+    pub stats: Option<Arc<dyn StatsSink>>,
+    pub complete_remaining: bool,
+}
+
+impl Runner {
+    pub fn new(scheduler: &Scheduler) -> Runner {
+        let timeout = match scheduler.timeout {
+            0 => None,
+            x => Some(Duration::from_millis(x as u64)),
+        };
+        Runner {
+            timeout,
+            quorum: None,
+            stats: None,
+            complete_remaining: false,
+        }
+    }
+
+    fn record(&self, start: Instant, node: &GraphNode, active: &Active, bytes: usize, outcome: Outcome) {
+        let sink = match &self.stats {
+            Some(v) => v,
+            None => {
+                return;
+            },
+        };
+        let first_byte = *active.first_byte.lock().unwrap();
+        let record = Record {
+            engine: node.engine.clone(),
+            url: node.url.to_string(),
+            offset: node.offset,
+            started: active.started.duration_since(start).as_millis() as u64,
+            first_byte: first_byte.map(|v| v.duration_since(active.started).as_millis() as u64),
+            total: active.started.elapsed().as_millis() as u64,
+            bytes: bytes as u64,
+            outcome,
+        };
+        sink.record(&record);
+    }
+
+    /// Execute the requests in the graph, using the [Adapter](crate::adapter::Adapter)s of the
+    /// [Registry] to fetch them.
+    ///
+    /// Returns the first successful [Response]. Unless `complete_remaining` is set, requests
+    /// still active at that time are cancelled, and requests not yet dispatched are never
+    /// started.
+    pub fn run(&self, graph: ControllerGraph, adapters: &Registry) -> Result<Response, RunError> {
+        let schedule: Vec<GraphNode> = graph.into_iter().collect();
+        if schedule.is_empty() {
+            return Err(RunError::Empty);
+        }
+
+        let (tx, rx) = mpsc::channel();
+        let start = Instant::now();
+        let mut next = 0;
+        let mut active: HashMap<usize, Active> = HashMap::new();
+        let mut failures: Vec<Failure> = vec![];
+        // one copy of the content of every group, with the requests that returned it
+        let mut results: Vec<(Vec<u8>, Vec<usize>)> = vec![];
+        let mut winner: Option<Response> = None;
+
+        loop {
+            let elapsed = start.elapsed();
+            while next < schedule.len() && Duration::from_millis(schedule[next].offset) <= elapsed {
+                let node = &schedule[next];
+                let url = node.url.to_string();
+                let engine = node.engine.clone();
+                let adapter = match adapters.adapter_for(&engine, &url) {
+                    Some(v) => v,
+                    None => {
+                        debug!("no adapter for request {}: {}", next, url);
+                        failures.push(Failure {
+                            url,
+                            engine,
+                            error: RequestError::NoAdapter,
+                        });
+                        next += 1;
+                        continue;
+                    },
+                };
+                let request = Request {
+                    offset: node.offset,
+                    url,
+                    engine,
+                    timeout: node.timeout.or(self.timeout),
+                    cancel: Arc::new(AtomicBool::new(false)),
+                    first_byte: Arc::new(Mutex::new(None)),
+                };
+                let started = Instant::now();
+                active.insert(next, Active {
+                    deadline: request.timeout.map(|t| started + t),
+                    started,
+                    cancel: request.cancel.clone(),
+                    first_byte: request.first_byte.clone(),
+                });
+
+                debug!("dispatch request {} at +{}ms: {}", next, request.offset, request.url);
+                let tx = tx.clone();
+                let i = next;
+                thread::spawn(move || {
+                    // a panicking adapter must still report back, or the run would wait for it
+                    // forever when there is no timeout
+                    let r = panic::catch_unwind(panic::AssertUnwindSafe(|| adapter.fetch(&request)));
+                    let r = r.unwrap_or_else(|e| {
+                        let reason = e.downcast_ref::<&str>().map(|v| v.to_string())
+                            .or_else(|| e.downcast_ref::<String>().cloned())
+                            .unwrap_or_default();
+                        Err(RequestError::Failed(format!("adapter panicked: {}", reason)))
+                    });
+                    let _r = tx.send((i, r));
+                });
+                next += 1;
+            }
+
+            if active.is_empty() && next == schedule.len() {
+                if let Some(r) = winner {
+                    return Ok(r);
+                }
+                if !results.is_empty() {
+                    let count = results.iter().map(|(_, v)| v.len()).sum();
+                    info!("no quorum from {} results", count);
+                    return Err(RunError::NoQuorum(count, failures));
+                }
+                info!("all {} requests failed", failures.len());
+                return Err(RunError::Exhausted(failures));
+            }
+
+            let now = Instant::now();
+            let mut wake: Option<Instant> = None;
+            if next < schedule.len() {
+                wake = Some(start + Duration::from_millis(schedule[next].offset));
+            }
+            for a in active.values() {
+                if let Some(d) = a.deadline {
+                    wake = Some(wake.map_or(d, |w| w.min(d)));
+                }
+            }
+
+            let received = match wake {
+                Some(w) => rx.recv_timeout(w.saturating_duration_since(now)).ok(),
+                None => rx.recv().ok(),
+            };
+
+            match received {
+                Some((i, Ok(mut content))) => {
+                    let a = match active.remove(&i) {
+                        Some(v) => v,
+                        None => {
+                            continue;
+                        },
+                    };
+                    let node = &schedule[i];
+                    if !node.validator.verify_trusted(&node.digest, Some(&content), node.signature.as_ref(), &node.trusted_keys) {
+                        info!("request {} returned invalid content: {}", i, node.url);
+                        self.record(start, node, &a, content.len(), Outcome::Invalid);
+                        failures.push(Failure {
+                            url: node.url.to_string(),
+                            engine: node.engine.clone(),
+                            error: RequestError::Invalid,
+                        });
+                        continue;
+                    }
+                    if winner.is_some() {
+                        self.record(start, &schedule[i], &a, content.len(), Outcome::Completed);
+                        continue;
+                    }
+                    let mut matches = vec![i];
+                    if let Some(quorum) = &self.quorum {
+                        let size = content.len();
+                        let k = match results.iter().position(|(v, _)| *v == content) {
+                            Some(k) => k,
+                            None => {
+                                results.push((content, vec![]));
+                                results.len() - 1
+                            },
+                        };
+                        let (group_content, group) = &mut results[k];
+                        group.push(i);
+                        let engines: Vec<&Engine> = group.iter().map(|j| &schedule[*j].engine).collect();
+                        if !quorum.is_met(&engines) {
+                            debug!("request {} has {} matching results", i, group.len());
+                            self.record(start, &schedule[i], &a, size, Outcome::Completed);
+                            continue;
+                        }
+                        matches = group.clone();
+                        // no further results are grouped once there is a winner
+                        content = mem::take(group_content);
+                    }
+                    info!("request {} won after {:?}: {}", i, start.elapsed(), node.url);
+                    self.record(start, &schedule[i], &a, content.len(), Outcome::Won);
+                    let response = Response {
+                        offset: node.offset,
+                        url: node.url.to_string(),
+                        engine: node.engine.clone(),
+                        content,
+                        matches: matches.iter().map(|j| schedule[*j].url.to_string()).collect(),
+                    };
+                    if self.complete_remaining {
+                        winner = Some(response);
+                        continue;
+                    }
+                    active.iter().for_each(|(j, a)| {
+                        a.cancel.store(true, Ordering::SeqCst);
+                        self.record(start, &schedule[*j], a, 0, Outcome::Cancelled);
+                    });
+                    return Ok(response);
+                },
+                Some((i, Err(e))) => {
+                    let a = match active.remove(&i) {
+                        Some(v) => v,
+                        None => {
+                            continue;
+                        },
+                    };
+                    debug!("request {} failed: {}", i, e);
+                    self.record(start, &schedule[i], &a, 0, Outcome::from(&e));
+                    failures.push(Failure {
+                        url: schedule[i].url.to_string(),
+                        engine: schedule[i].engine.clone(),
+                        error: e,
+                    });
+                },
+                None => {
+                    let now = Instant::now();
+                    let expired: Vec<usize> = active.iter()
+                        .filter(|(_, a)| a.deadline.is_some_and(|d| d <= now))
+                        .map(|(i, _)| *i)
+                        .collect();
+                    for i in expired {
+                        let a = active.remove(&i).unwrap();
+                        a.cancel.store(true, Ordering::SeqCst);
+                        debug!("request {} timed out", i);
+                        self.record(start, &schedule[i], &a, 0, Outcome::Timeout);
+                        failures.push(Failure {
+                            url: schedule[i].url.to_string(),
+                            engine: schedule[i].engine.clone(),
+                            error: RequestError::Timeout,
+                        });
+                    }
+                },
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        Arc,
+        Mutex,
+    };
+    use std::thread;
+    use std::time::{
+        Duration,
+        Instant,
+    };
+
+    use url::Url;
+
+    use super::{
+        Quorum,
+        Runner,
+        RunError,
+        Request,
+        RequestError,
+    };
+    use crate::adapter::{
+        Adapter,
+        Registry,
+    };
+    use crate::control::Controller;
+    use crate::control::graph::{
+        ControllerGraph,
+        GraphNode,
+    };
+    use crate::endpoint::Endpoint;
+    use crate::keystore::{
+        Keystore,
+        MemoryKeystore,
+    };
+    use crate::mock::{
+        TestResolverItem,
+        TestSignedResolverItem,
+        TestSignatureVerifier,
+        TestValidator,
+    };
+    use crate::resolver::Resolver;
+    use crate::source::Source;
+    use crate::stats::{
+        MemorySink,
+        Outcome,
+    };
+    use crate::timing::Scheduler;
+    use crate::validator::SignatureValidator;
+
+    static TEST_VALIDATOR: TestValidator = TestValidator{};
+
+    fn graph() -> ControllerGraph {
+        let mut g = ControllerGraph::new();
+        let foo = "foo".to_string();
+        let bar = "bar".to_string();
+        g.add(0, &foo, "mock://one/deadbeef".to_string());
+        g.add(50, &bar, "mock://two/deadbeef".to_string());
+        g.add(100, &foo, "mock://three/deadbeef".to_string());
+        g
+    }
+
+    fn adapters<F>(f: F) -> Registry
+    where F: Fn(&Request) -> Result<Vec<u8>, RequestError> + Send + Sync + 'static {
+        let mut registry = Registry::new();
+        let adapter: Arc<dyn Adapter> = Arc::new(f);
+        registry.add_scheme("mock", adapter);
+        registry
+    }
+
+    fn scheduler(timeout: u32) -> Scheduler {
+        Scheduler {
+            delay: 0,
+            timeout,
+        }
+    }
+
+    #[test]
+    fn test_runner_first_wins() {
+        let runner = Runner::new(&scheduler(0));
+        let r = runner.run(graph(), &adapters(|request: &Request| {
+            Ok(request.url.as_bytes().to_vec())
+        })).unwrap();
+        assert_eq!(r.offset, 0);
+        assert_eq!(r.content, b"mock://one/deadbeef");
+    }
+
+    #[test]
+    fn test_runner_fallback_cancels_rest() {
+        let cancelled = Arc::new(Mutex::new(vec![]));
+        let cancelled_fetch = cancelled.clone();
+        let runner = Runner::new(&scheduler(0));
+        let start = Instant::now();
+        let r = runner.run(graph(), &adapters(move |request: &Request| {
+            match request.url.as_str() {
+                "mock://one/deadbeef" => {
+                    while !request.is_cancelled() {
+                        thread::sleep(Duration::from_millis(5));
+                    }
+                    cancelled_fetch.lock().unwrap().push(request.url.clone());
+                    Err(RequestError::Cancelled)
+                },
+                "mock://two/deadbeef" => Ok(vec![42]),
+                _ => panic!("request {} should not be dispatched", request.url),
+            }
+        })).unwrap();
+        assert_eq!(r.engine, "bar");
+        assert_eq!(r.content, vec![42]);
+        assert!(start.elapsed() >= Duration::from_millis(50));
+
+        // the first request only returns once it has been cancelled
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while cancelled.lock().unwrap().is_empty() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(*cancelled.lock().unwrap(), vec!["mock://one/deadbeef".to_string()]);
+    }
+
+    #[test]
+    fn test_runner_timeout() {
+        let runner = Runner::new(&scheduler(20));
+        let r = runner.run(graph(), &adapters(|request: &Request| {
+            while !request.is_cancelled() {
+                thread::sleep(Duration::from_millis(5));
+            }
+            Err(RequestError::Cancelled)
+        }));
+        match r {
+            Err(RunError::Exhausted(failures)) => {
+                assert_eq!(failures.len(), 3);
+                failures.iter().for_each(|failure| {
+                    assert_eq!(failure.error, RequestError::Timeout);
+                });
+            },
+            _ => panic!("expected all requests to time out"),
+        };
+    }
+
+    #[test]
+    fn test_runner_node_timeout() {
+        let mut g = ControllerGraph::new();
+        let mut node = GraphNode::new(0, &"foo".to_string(), Url::parse("mock://slow/deadbeef").unwrap());
+        node.timeout = Some(Duration::from_millis(20));
+        g.add_node(node);
+        g.add(50, &"bar".to_string(), "mock://fast/deadbeef".to_string());
+
+        let runner = Runner::new(&scheduler(0));
+        let r = runner.run(g, &adapters(|request: &Request| {
+            if request.url == "mock://slow/deadbeef" {
+                assert_eq!(request.timeout, Some(Duration::from_millis(20)));
+                while !request.is_cancelled() {
+                    thread::sleep(Duration::from_millis(5));
+                }
+                return Err(RequestError::Cancelled);
+            }
+            assert_eq!(request.timeout, None);
+            Ok(vec![1, 2, 3])
+        })).unwrap();
+        assert_eq!(r.url, "mock://fast/deadbeef");
+    }
+
+    #[test]
+    fn test_runner_adapter_panic() {
+        let runner = Runner::new(&scheduler(0));
+        let r = runner.run(graph(), &adapters(|request: &Request| {
+            match request.url.as_str() {
+                "mock://three/deadbeef" => Ok(vec![42]),
+                _ => panic!("adapter bug"),
+            }
+        })).unwrap();
+        assert_eq!(r.url, "mock://three/deadbeef");
+
+        let r = runner.run(graph(), &adapters(|_: &Request| -> Result<Vec<u8>, RequestError> {
+            panic!("adapter bug")
+        }));
+        match r {
+            Err(RunError::Exhausted(failures)) => {
+                assert_eq!(failures.len(), 3);
+                assert_eq!(failures[0].error, RequestError::Failed("adapter panicked: adapter bug".to_string()));
+            },
+            _ => panic!("expected panicking adapters to fail"),
+        };
+    }
+
+    #[test]
+    fn test_runner_empty() {
+        let runner = Runner::new(&scheduler(0));
+        let r = runner.run(ControllerGraph::new(), &Registry::new());
+        assert!(matches!(r, Err(RunError::Empty)));
+    }
+
+    #[test]
+    fn test_runner_engine_adapter() {
+        let mut registry = adapters(|_: &Request| Err(RequestError::Failed("unavailable".to_string())));
+        registry.add_engine("bar".to_string(), Arc::new(|_: &Request| Ok(vec![13])));
+        let runner = Runner::new(&scheduler(0));
+        let r = runner.run(graph(), &registry).unwrap();
+        assert_eq!(r.engine, "bar");
+        assert_eq!(r.content, vec![13]);
+    }
+
+    #[test]
+    fn test_runner_no_adapter() {
+        let runner = Runner::new(&scheduler(0));
+        let r = runner.run(graph(), &Registry::new());
+        match r {
+            Err(RunError::Exhausted(failures)) => {
+                assert_eq!(failures.len(), 3);
+                assert_eq!(failures[0].error, RequestError::NoAdapter);
+            },
+            _ => panic!("expected missing adapters to fail"),
+        };
+    }
+
+    #[test]
+    fn test_runner_verify() {
+        let mut source = Source::new("foo".to_string());
+        source.endpoints.push(Endpoint::new("mock://one", Some(&TEST_VALIDATOR)));
+        source.endpoints.push(Endpoint::new("mock://two", Some(&TEST_VALIDATOR)));
+        source.endpoints.push(Endpoint::new("mock://three", None));
+        source.timing = Some(Scheduler {
+            delay: 10,
+            timeout: 0,
+        });
+        let mut ctrl = Controller::new(scheduler(0));
+        ctrl.add(source);
+
+        let mut resolver = Resolver::new();
+        let _r = resolver.add("foo".to_string(), Box::new(TestResolverItem{key: vec![1, 2, 3]}));
+
+        let runner = Runner::new(&scheduler(0));
+        let g = ctrl.generate(&resolver);
+        let r = runner.run(g.clone(), &adapters(|request: &Request| {
+            match request.url.as_str() {
+                "mock://one/010203" => Ok(vec![6, 6, 6]),
+                "mock://two/010203" => Ok(vec![1, 2, 3]),
+                _ => Err(RequestError::NotFound),
+            }
+        })).unwrap();
+        assert_eq!(r.url, "mock://two/010203");
+        assert_eq!(r.content, vec![1, 2, 3]);
+
+        let r = runner.run(g, &adapters(|_: &Request| {
+            Ok(vec![6, 6, 6])
+        })).unwrap();
+        assert_eq!(r.url, "mock://three/010203");
+    }
+
+    #[test]
+    fn test_runner_quorum() {
+        let mut g = graph();
+        g.add(150, &"bar".to_string(), "mock://four/deadbeef".to_string());
+        let fetch = |request: &Request| {
+            match request.url.as_str() {
+                "mock://one/deadbeef" => Ok(vec![6, 6, 6]),
+                _ => Ok(vec![1, 2, 3]),
+            }
+        };
+
+        let mut runner = Runner::new(&scheduler(0));
+        runner.quorum = Some(Quorum::new(2, false));
+        let r = runner.run(g, &adapters(fetch)).unwrap();
+        assert_eq!(r.content, vec![1, 2, 3]);
+        assert_eq!(r.url, "mock://three/deadbeef");
+        assert_eq!(r.matches, vec!["mock://two/deadbeef", "mock://three/deadbeef"]);
+
+        let mut g = graph();
+        g.add(150, &"bar".to_string(), "mock://four/deadbeef".to_string());
+        runner.quorum = Some(Quorum::new(2, true));
+        let r = runner.run(g, &adapters(fetch)).unwrap();
+        assert_eq!(r.content, vec![1, 2, 3]);
+        assert_eq!(r.url, "mock://three/deadbeef");
+
+        let mut g = graph();
+        g.add(150, &"bar".to_string(), "mock://four/deadbeef".to_string());
+        runner.quorum = Some(Quorum::new(3, true));
+        let r = runner.run(g, &adapters(fetch));
+        assert!(matches!(r, Err(RunError::NoQuorum(4, _))));
+    }
+
+    #[test]
+    fn test_runner_stats() {
+        let fetch = |request: &Request| {
+            match request.url.as_str() {
+                "mock://one/deadbeef" => {
+                    while !request.is_cancelled() {
+                        thread::sleep(Duration::from_millis(5));
+                    }
+                    Err(RequestError::Cancelled)
+                },
+                "mock://two/deadbeef" => {
+                    request.mark_first_byte();
+                    Ok(vec![1, 2, 3])
+                },
+                _ => Ok(vec![4, 5]),
+            }
+        };
+
+        let sink = Arc::new(MemorySink::new());
+        let mut runner = Runner::new(&scheduler(0));
+        runner.stats = Some(sink.clone());
+        let r = runner.run(graph(), &adapters(fetch)).unwrap();
+        assert_eq!(r.url, "mock://two/deadbeef");
+
+        let records = sink.records();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].url, "mock://two/deadbeef");
+        assert_eq!(records[0].outcome, Outcome::Won);
+        assert_eq!(records[0].offset, 50);
+        assert!(records[0].started >= 50);
+        assert!(records[0].first_byte.is_some());
+        assert_eq!(records[0].bytes, 3);
+        assert_eq!(records[1].url, "mock://one/deadbeef");
+        assert_eq!(records[1].outcome, Outcome::Cancelled);
+        assert!(records[1].total >= 50);
+    }
+
+    #[test]
+    fn test_runner_complete_remaining() {
+        let sink = Arc::new(MemorySink::new());
+        let mut runner = Runner::new(&scheduler(0));
+        runner.stats = Some(sink.clone());
+        runner.complete_remaining = true;
+        let r = runner.run(graph(), &adapters(|request: &Request| {
+            Ok(request.url.as_bytes().to_vec())
+        })).unwrap();
+        assert_eq!(r.url, "mock://one/deadbeef");
+
+        let records = sink.records();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].outcome, Outcome::Won);
+        assert_eq!(records[1].outcome, Outcome::Completed);
+        assert_eq!(records[2].outcome, Outcome::Completed);
+        assert_eq!(records[2].url, "mock://three/deadbeef");
+        assert_eq!(records[2].first_byte, None);
+    }
+
+    #[test]
+    fn test_runner_trusted_keys() {
+        let mut keystore = MemoryKeystore::new();
+        let fp_foo = keystore.add(vec![1]);
+        let fp_bar = keystore.add(vec![2]);
+        let keystore: &'static MemoryKeystore = Box::leak(Box::new(keystore));
+        let validator: &'static SignatureValidator = Box::leak(Box::new(SignatureValidator::new(keystore, &TestSignatureVerifier{}, &TEST_VALIDATOR)));
+
+        let mut source_bar = Source::new("bar".to_string());
+        source_bar.endpoints.push(Endpoint::new("mock://bar", Some(validator)));
+        source_bar.trusted_keys.push(fp_bar);
+        source_bar.timing = Some(scheduler(0));
+        let mut source_foo = Source::new("foo".to_string());
+        source_foo.endpoints.push(Endpoint::new("mock://foo", Some(validator)));
+        source_foo.trusted_keys.push(fp_foo);
+        source_foo.timing = Some(scheduler(0));
+        let mut ctrl = Controller::new(Scheduler {
+            delay: 10,
+            timeout: 0,
+        });
+        ctrl.add(source_bar);
+        ctrl.add(source_foo);
+
+        let mut resolver = Resolver::new();
+        let signature = vec![1, 1, 2, 3];
+        let mut _r = resolver.add("foo".to_string(), Box::new(TestSignedResolverItem{key: vec![1, 2, 3], signature: signature.clone()}));
+        _r = resolver.add("bar".to_string(), Box::new(TestSignedResolverItem{key: vec![1, 2, 3], signature}));
+
+        let runner = Runner::new(&scheduler(0));
+        let r = runner.run(ctrl.generate(&resolver), &adapters(|_: &Request| Ok(vec![1, 2, 3]))).unwrap();
+        assert_eq!(r.engine, "foo");
+    }
+
+    #[test]
+    fn test_runner_tampered_content() {
+        let mut keystore = MemoryKeystore::new();
+        let fp = keystore.add(vec![1]);
+        let keystore: &'static MemoryKeystore = Box::leak(Box::new(keystore));
+        let validator: &'static SignatureValidator = Box::leak(Box::new(SignatureValidator::new(keystore, &TestSignatureVerifier{}, &TEST_VALIDATOR)));
+
+        let mut source = Source::new("foo".to_string());
+        source.endpoints.push(Endpoint::new("mock://evil", Some(validator)));
+        source.endpoints.push(Endpoint::new("mock://good", Some(validator)));
+        source.trusted_keys.push(fp);
+        source.timing = Some(Scheduler {
+            delay: 10,
+            timeout: 0,
+        });
+        let mut ctrl = Controller::new(scheduler(0));
+        ctrl.add(source);
+
+        let mut resolver = Resolver::new();
+        let _r = resolver.add("foo".to_string(), Box::new(TestSignedResolverItem{key: vec![1, 2, 3], signature: vec![1, 1, 2, 3]}));
+
+        let runner = Runner::new(&scheduler(0));
+        let g = ctrl.generate(&resolver);
+        let r = runner.run(g.clone(), &adapters(|request: &Request| {
+            match request.url.as_str() {
+                "mock://evil/010203" => Ok(vec![6, 6, 6]),
+                _ => Ok(vec![1, 2, 3]),
+            }
+        })).unwrap();
+        assert_eq!(r.url, "mock://good/010203");
+
+        let r = runner.run(g, &adapters(|_: &Request| Ok(vec![6, 6, 6])));
+        match r {
+            Err(RunError::Exhausted(failures)) => {
+                assert_eq!(failures.len(), 2);
+                failures.iter().for_each(|failure| {
+                    assert_eq!(failure.error, RequestError::Invalid);
+                });
+            },
+            _ => panic!("expected tampered content to be rejected"),
+        };
+    }
+}
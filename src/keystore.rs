@@ -1,5 +1,12 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use log::debug;
+
 /// Verbatim public [Key] bytes.
-pub type Key = Vec<u8>; 
+pub type Key = Vec<u8>;
 
 /// Context-dependent fingerprint of public [Key].
 pub type Fingerprint = Vec<u8>;
@@ -9,13 +16,146 @@ pub trait Keystore {
 
     /// Add a new public [Key]
     ///
-    /// Returns the length of the [Key] added.
-    fn add(key: Key) -> usize;
+    /// Returns the [Fingerprint] of the [Key] added.
+    fn add(&mut self, key: Key) -> Fingerprint;
 
     /// Returns true if the public [Key] corresponding to the [Fingerprint] exists in the
     /// [Keystore].
-    fn have(fp: Fingerprint) -> bool;
+    fn have(&self, fp: &Fingerprint) -> bool;
 
     /// Retrieve the [Key] corresponding to the given [Fingerprint].
-    fn get(fp: Fingerprint) -> Result<Key, ()>;
+    fn get(&self, fp: &Fingerprint) -> Option<&Key>;
+
+    /// Returns the [Fingerprint]s of all keys in the [Keystore].
+    fn fingerprints(&self) -> Vec<Fingerprint>;
+}
+
+/// The default fingerprint function, using the [Key] itself as its [Fingerprint].
+///
+/// This is suitable for schemes with short public keys, like ed25519.
+pub fn key_fingerprint(key: &Key) -> Fingerprint {
+    key.clone()
+}
+
+/// [MemoryKeystore] keeps public keys in memory.
+///
+/// The [Fingerprint] of a key is calculated by the fingerprint function given at construction,
+/// which defaults to [key_fingerprint].
+pub struct MemoryKeystore {
+    keys: HashMap<Fingerprint, Key>,
+    fingerprint: fn(&Key) -> Fingerprint,
+}
+
+impl Default for MemoryKeystore {
+    fn default() -> MemoryKeystore {
+        MemoryKeystore::new()
+    }
+}
+
+impl MemoryKeystore {
+    pub fn new() -> MemoryKeystore {
+        MemoryKeystore::with_fingerprint(key_fingerprint)
+    }
+
+    pub fn with_fingerprint(fingerprint: fn(&Key) -> Fingerprint) -> MemoryKeystore {
+        MemoryKeystore {
+            keys: HashMap::new(),
+            fingerprint,
+        }
+    }
+
+    /// Add all keys found in a directory.
+    ///
+    /// Every regular file in the directory is expected to contain a single key. If the
+    /// contents of the file is a hex string, the key is the decoded value. Otherwise the file
+    /// contents are used verbatim.
+    ///
+    /// Returns the number of keys added.
+    pub fn load_dir(&mut self, path: &Path) -> Result<usize, io::Error> {
+        let mut count = 0;
+        for entry in fs::read_dir(path)? {
+            let entry = entry?;
+            if !entry.file_type()?.is_file() {
+                continue;
+            }
+            let data = fs::read(entry.path())?;
+            let key = match std::str::from_utf8(&data).ok().and_then(|v| hex::decode(v.trim()).ok()) {
+                Some(v) => v,
+                None => data,
+            };
+            let fp = self.add(key);
+            debug!("loaded key {} from {:?}", hex::encode(&fp), entry.path());
+            count += 1;
+        }
+        Ok(count)
+    }
+}
+
+impl Keystore for MemoryKeystore {
+    fn add(&mut self, key: Key) -> Fingerprint {
+        let fp = (self.fingerprint)(&key);
+        self.keys.insert(fp.clone(), key);
+        fp
+    }
+
+    fn have(&self, fp: &Fingerprint) -> bool {
+        self.keys.contains_key(fp)
+    }
+
+    fn get(&self, fp: &Fingerprint) -> Option<&Key> {
+        self.keys.get(fp)
+    }
+
+    fn fingerprints(&self) -> Vec<Fingerprint> {
+        self.keys.keys().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    use super::{
+        Key,
+        Fingerprint,
+        Keystore,
+        MemoryKeystore,
+    };
+
+    fn reverse_fingerprint(key: &Key) -> Fingerprint {
+        key.iter().rev().cloned().collect()
+    }
+
+    #[test]
+    fn test_keystore_memory() {
+        let mut keystore = MemoryKeystore::new();
+        let fp = keystore.add(vec![1, 2, 3]);
+        assert_eq!(fp, vec![1, 2, 3]);
+        assert!(keystore.have(&fp));
+        assert!(!keystore.have(&vec![3, 2, 1]));
+        assert_eq!(keystore.get(&fp), Some(&vec![1, 2, 3]));
+
+        let mut keystore = MemoryKeystore::with_fingerprint(reverse_fingerprint);
+        let fp = keystore.add(vec![1, 2, 3]);
+        assert_eq!(fp, vec![3, 2, 1]);
+        assert_eq!(keystore.get(&fp), Some(&vec![1, 2, 3]));
+        assert_eq!(keystore.fingerprints(), vec![vec![3, 2, 1]]);
+    }
+
+    #[test]
+    fn test_keystore_dir() {
+        let dir = env::temp_dir().join(format!("fadfada-keys-{}", process::id()));
+        fs::create_dir_all(dir.join("subdir")).unwrap();
+        fs::write(dir.join("foo.pub"), "010203\n").unwrap();
+        fs::write(dir.join("bar.pub"), vec![0xff, 0xfe]).unwrap();
+
+        let mut keystore = MemoryKeystore::new();
+        assert_eq!(keystore.load_dir(&dir).unwrap(), 2);
+        assert!(keystore.have(&vec![1, 2, 3]));
+        assert!(keystore.have(&vec![0xff, 0xfe]));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
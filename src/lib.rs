@@ -11,6 +11,13 @@
 //!
 //! The execution of `fadfada` is described by registering resources and schedules with the
 //! `fadfada::control::Controller` object.
+//!
+//! The resulting request graph can be executed with the `fadfada::exec::Runner` object, which
+//! dispatches each request at its offset and returns the first result retrieved.
+
+/// Errors raised by the crate.
+pub mod error;
+pub use error::Error;
 
 /// A single endpoint in a source structure.
 pub mod endpoint;
@@ -33,11 +40,34 @@ pub mod source;
 /// Entry-point object that orchestrates order and timing of requests.
 pub mod control;
 
+/// Describes many named resources for retrieval with a single controller.
+pub mod manifest;
+
+/// Retrieves content from a specific type of endpoint.
+pub mod adapter;
+
+/// Executes the requests of a generated request graph.
+pub mod exec;
+
+/// Records request statistics for benchmarking endpoints.
+pub mod stats;
+
+/// Scores endpoints from historical request statistics.
+pub mod score;
+
+/// Serialization of the configuration objects with serde.
+#[cfg(feature = "serde")]
+mod serialize;
+
 #[cfg(feature = "web2")]
 pub mod web2;
 
 #[cfg(feature = "yaml")]
 pub mod yaml;
 
+/// Validates content signed with ed25519 keys.
+#[cfg(feature = "ed25519")]
+pub mod ed25519;
+
 #[cfg(test)]
 pub mod mock;
@@ -0,0 +1,212 @@
+use std::collections::BTreeMap;
+
+use crate::control::Controller;
+use crate::control::graph::ControllerGraph;
+use crate::error::Error;
+use crate::resolver::{
+    Digest,
+    ErrorDetail,
+    Resolver,
+    ResolverError,
+    ResolverItem,
+    Signature,
+};
+use crate::source::Engine;
+
+/// A single resource of a [Manifest].
+pub struct Resource {
+    /// Hex pointers to the resource for each [Engine] it can be retrieved from.
+    pub pointers: BTreeMap<Engine, String>,
+    /// Size of the resource in bytes.
+    ///
+    /// Informational only; retrieved content is not checked against it.
+    pub size: Option<u64>,
+    /// Media type of the resource, e.g. `text/html`.
+    ///
+    /// Informational only, e.g. for serving the content once it is retrieved.
+    pub content_type: Option<String>,
+    /// Detached signature over the digest of the resource.
+    pub signature: Option<Signature>,
+}
+
+impl Default for Resource {
+    fn default() -> Resource {
+        Resource::new()
+    }
+}
+
+impl Resource {
+    pub fn new() -> Resource {
+        Resource {
+            pointers: BTreeMap::new(),
+            size: None,
+            content_type: None,
+            signature: None,
+        }
+    }
+
+    /// Create a [Resolver] with an entry for every engine the resource has a pointer for.
+    ///
+    /// Will error with [Error::Decode] if a pointer is not a valid hex string.
+    pub fn resolver(&self) -> Result<Resolver, Error> {
+        let mut resolver = Resolver::new();
+        for (engine, pointer) in self.pointers.iter() {
+            let digest = match hex::decode(pointer) {
+                Ok(v) => v,
+                Err(e) => {
+                    return Err(Error::Decode(format!("invalid hex pointer '{}': {}", pointer, e)));
+                },
+            };
+            let item = ResourceItem {
+                digest,
+                pointer: pointer.clone(),
+                signature: self.signature.clone(),
+            };
+            resolver.add(engine.clone(), Box::new(item))?;
+        }
+        Ok(resolver)
+    }
+}
+
+struct ResourceItem {
+    digest: Digest,
+    pointer: String,
+    signature: Option<Signature>,
+}
+
+impl ResolverItem for ResourceItem {
+    fn digest(&self) -> &Digest {
+        &self.digest
+    }
+
+    fn signature(&self) -> Result<Signature, ResolverError> {
+        self.signature.clone().ok_or_else(|| ResolverError::new(ErrorDetail::MissingSignatureError))
+    }
+
+    fn pointer(&self) -> String {
+        self.pointer.clone()
+    }
+}
+
+/// Lists named resources, so that all the content of an application can be retrieved with a
+/// single [Controller].
+///
+/// Where the `content` document of the [yaml](crate::yaml) module describes a single resource,
+/// a manifest describes any number of them:
+///
+/// ``` ignore,
+/// resources:
+///   index.html:
+///     content_type: text/html
+///     size: 1024
+///     pointers:
+///       foo: deadbeef
+///       bar: beeffeed
+///   app.js:
+///     content_type: application/javascript
+///     signature: "0102"
+///     pointers:
+///       foo: feedbeef
+/// ```
+///
+/// With the `yaml` feature, this document is parsed with the `FromYaml` implementation of the
+/// `yaml` module. Signatures are given as hex strings.
+pub struct Manifest {
+    pub resources: BTreeMap<String, Resource>,
+}
+
+impl Default for Manifest {
+    fn default() -> Manifest {
+        Manifest::new()
+    }
+}
+
+impl Manifest {
+    pub fn new() -> Manifest {
+        Manifest {
+            resources: BTreeMap::new(),
+        }
+    }
+
+    /// Create a [Resolver] for the named resource.
+    ///
+    /// Will error with [Error::UnknownResource] if the manifest has no resource with the name.
+    pub fn resolver(&self, name: &str) -> Result<Resolver, Error> {
+        match self.resources.get(name) {
+            Some(v) => v.resolver(),
+            None => Err(Error::UnknownResource(name.to_string())),
+        }
+    }
+
+    /// Generate the [ControllerGraph] retrieving the named resource.
+    ///
+    /// As with [Controller::generate], sources whose engine the resource has no pointer for are
+    /// left out of the graph.
+    pub fn generate(&self, ctrl: &Controller, name: &str) -> Result<ControllerGraph, Error> {
+        let resolver = self.resolver(name)?;
+        Ok(ctrl.graph(&resolver))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Manifest,
+        Resource,
+    };
+    use crate::control::Controller;
+    use crate::endpoint::Endpoint;
+    use crate::error::Error;
+    use crate::source::Source;
+    use crate::timing::Scheduler;
+
+    fn manifest() -> Manifest {
+        let mut manifest = Manifest::new();
+        let mut resource = Resource::new();
+        resource.pointers.insert("foo".to_string(), "deadbeef".to_string());
+        resource.pointers.insert("bar".to_string(), "beeffeed".to_string());
+        manifest.resources.insert("index.html".to_string(), resource);
+        let mut resource = Resource::new();
+        resource.pointers.insert("foo".to_string(), "feedbeef".to_string());
+        resource.signature = Some(vec![1, 2]);
+        manifest.resources.insert("app.js".to_string(), resource);
+        manifest
+    }
+
+    #[test]
+    fn test_manifest_generate() {
+        let mut ctrl = Controller::new(Scheduler {
+            delay: 100,
+            timeout: 0,
+        });
+        for (engine, url) in [("foo", "https://one.foo.com"), ("bar", "https://only.bar.com")].iter() {
+            let mut source = Source::new(engine.to_string());
+            source.endpoints.push(Endpoint::new(url, None));
+            ctrl.add(source);
+        }
+        let manifest = manifest();
+
+        let v: Vec<String> = manifest.generate(&ctrl, "index.html").unwrap().iter().map(|v| v.url.to_string()).collect();
+        assert_eq!(v, vec!["https://one.foo.com/deadbeef", "https://only.bar.com/beeffeed"]);
+
+        let g = manifest.generate(&ctrl, "app.js").unwrap();
+        assert_eq!(g.len(), 1);
+        assert_eq!(g.skipped()[0].engine, "bar");
+        assert_eq!(g[0].url.as_str(), "https://one.foo.com/feedbeef");
+        assert_eq!(g[0].digest, vec![0xfe, 0xed, 0xbe, 0xef]);
+        assert_eq!(g[0].signature, Some(vec![1, 2]));
+
+        assert!(matches!(manifest.generate(&ctrl, "foo.css"), Err(Error::UnknownResource(v)) if v == "foo.css"));
+    }
+
+    #[test]
+    fn test_manifest_resolver() {
+        let mut manifest = manifest();
+        let resolver = manifest.resolver("index.html").unwrap();
+        assert_eq!(resolver.engines(), vec!["bar", "foo"]);
+        assert!(resolver.item_for(&"foo".to_string()).unwrap().signature().is_err());
+
+        manifest.resources.get_mut("app.js").unwrap().pointers.insert("bar".to_string(), "xyzzy".to_string());
+        assert!(matches!(manifest.resolver("app.js"), Err(Error::Decode(_))));
+    }
+}
@@ -1,4 +1,8 @@
-use hex;
+use crate::keystore::Key;
+use crate::validator::{
+    SignatureVerifier,
+    Validator,
+};
 use crate::resolver::{
         ResolverItem,
         ResolverError,
@@ -10,16 +14,55 @@ pub struct TestResolverItem {
     pub key: Digest,
 }
 
-impl<'r> ResolverItem for TestResolverItem {
+impl ResolverItem for TestResolverItem {
     fn digest(&self) -> &Digest {
-        return &self.key;
+        &self.key
     }
 
     fn pointer(&self) -> String {
-        return hex::encode(&self.key);
+        hex::encode(&self.key)
     }
 
     fn signature(&self) -> Result<Signature, ResolverError> {
         Ok(vec![])
     }
 }
+
+pub struct TestSignedResolverItem {
+    pub key: Digest,
+    pub signature: Signature,
+}
+
+impl ResolverItem for TestSignedResolverItem {
+    fn digest(&self) -> &Digest {
+        &self.key
+    }
+
+    fn pointer(&self) -> String {
+        hex::encode(&self.key)
+    }
+
+    fn signature(&self) -> Result<Signature, ResolverError> {
+        Ok(self.signature.clone())
+    }
+}
+
+/// Accepts content only if it is identical to the digest.
+pub struct TestValidator {
+}
+
+impl Validator for TestValidator {
+    fn verify(&self, digest: &Digest, content: Option<&Vec<u8>>, _signature: Option<&Signature>) -> bool {
+        content == Some(digest)
+    }
+}
+
+/// Accepts signatures consisting of the key followed by the digest.
+pub struct TestSignatureVerifier {
+}
+
+impl SignatureVerifier for TestSignatureVerifier {
+    fn verify_signature(&self, key: &Key, digest: &Digest, signature: &Signature) -> bool {
+        *signature == [key.as_slice(), digest.as_slice()].concat()
+    }
+}
@@ -8,45 +8,69 @@ use hex;
 pub type Digest = Vec<u8>;
 pub type Signature = Vec<u8>;
 use crate::source;
+use crate::error::Error;
 
 
 /// ErrorDetail adds detail to the `ResolverError` type raised on any error occurring within the
 /// resolver package.
+#[derive(Debug, Clone, PartialEq)]
 pub enum ErrorDetail {
     EngineExistsError,
     UnknownEngineError,
+    MissingSignatureError,
 }
 
 /// ResolverError encapsulates any error raised within the resolver package.
+#[derive(Debug, Clone)]
 pub struct ResolverError {
     detail: ErrorDetail,
+    engine: Option<source::Engine>,
 }
 
 impl ResolverError {
     pub fn new(e: ErrorDetail) -> ResolverError {
         ResolverError {
             detail: e,
+            engine: None,
         } 
     }
+
+    /// Create an error for a specific [source::Engine].
+    pub fn for_engine(e: ErrorDetail, engine: source::Engine) -> ResolverError {
+        ResolverError {
+            detail: e,
+            engine: Some(engine),
+        }
+    }
+
+    pub fn detail(&self) -> &ErrorDetail {
+        &self.detail
+    }
+
+    /// The [source::Engine] the error was raised for, if known.
+    pub fn engine(&self) -> Option<&source::Engine> {
+        self.engine.as_ref()
+    }
 }
 
 impl fmt::Display for ResolverError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let engine = self.engine.as_deref().unwrap_or("");
         match &self.detail {
             ErrorDetail::EngineExistsError => {
-                return fmt::write(f, format_args!("Resolver error display"));
+                fmt::write(f, format_args!("engine '{}' already exists in resolver", engine))
             },
             ErrorDetail::UnknownEngineError => {
-                return fmt::write(f, format_args!("Resolver error display"));
+                fmt::write(f, format_args!("no resolver entry for engine '{}'", engine))
             },
-        };
+            ErrorDetail::MissingSignatureError => {
+                fmt::write(f, format_args!("no signature for resolver item"))
+            },
+        }
     }
 }
 
-impl fmt::Debug for ResolverError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Resolver error debug")
-    }
+impl std::error::Error for ResolverError {
 }
 
 
@@ -78,26 +102,40 @@ pub struct SimpleResolverItem {
 
 impl ResolverItem for SimpleResolverItem {
     fn digest(&self) -> &Digest {
-        return &self.digest;
+        &self.digest
     }
 
     fn pointer(&self) -> String {
-        return self.src.clone();
+        self.src.clone()
     }
 
     fn signature(&self) -> Result<Signature, ResolverError> {
-        return Err(ResolverError{
-            detail: ErrorDetail::UnknownEngineError,
-        });
+        Err(ResolverError::new(ErrorDetail::MissingSignatureError))
     }
 } 
 
 impl SimpleResolverItem {
+    /// Create a resolver item from a hex pointer.
+    ///
+    /// Panics if the pointer is not a valid hex string; see [SimpleResolverItem::try_new].
     pub fn new(content: String) -> SimpleResolverItem {
-        SimpleResolverItem{
-            digest: hex::decode(&content).unwrap(),
+        SimpleResolverItem::try_new(content).unwrap()
+    }
+
+    /// Create a resolver item from a hex pointer.
+    ///
+    /// Will error if the pointer is not a valid hex string.
+    pub fn try_new(content: String) -> Result<SimpleResolverItem, Error> {
+        let digest = match hex::decode(&content) {
+            Ok(v) => v,
+            Err(e) => {
+                return Err(Error::Decode(format!("invalid hex pointer '{}': {}", content, e)));
+            },
+        };
+        Ok(SimpleResolverItem{
+            digest,
             src: content,
-        }
+        })
     }
 }
 
@@ -112,6 +150,12 @@ pub struct Resolver {
 }
 
 
+impl Default for Resolver {
+    fn default() -> Resolver {
+        Resolver::new()
+    }
+}
+
 impl Resolver {
     pub fn new() -> Resolver {
         Resolver {
@@ -124,7 +168,7 @@ impl Resolver {
     /// Will error if a record for [source::Engine] already exists.
     pub fn add(&mut self, e: source::Engine, r: Box<dyn ResolverItem>) -> Result<(), ResolverError> {
         if self.resolvers.contains_key(&e) {
-            let e = ResolverError::new(ErrorDetail::EngineExistsError);
+            let e = ResolverError::for_engine(ErrorDetail::EngineExistsError, e);
             return Err(e);
         }
         debug!("added engine {}", e);
@@ -133,6 +177,20 @@ impl Resolver {
     }
 
     /// Retrieve the [ResolverItem] registered for an [source::Engine].
+    ///
+    /// Will error if a record for `Engine` doesn't exist.
+    pub fn item_for(&self, e: &source::Engine) -> Result<&dyn ResolverItem, ResolverError> {
+        match self.resolvers.get(e) {
+            Some(x) => {
+                Ok(x.as_ref())
+            },
+            None => {
+                Err(ResolverError::for_engine(ErrorDetail::UnknownEngineError, e.clone()))
+            },
+        }
+    }
+
+    /// Retrieve the pointer of the [ResolverItem] registered for an [source::Engine].
     /// 
     /// Will error if a record for `Engine` doesn't exist.
     pub fn pointer_for(&self, e: &source::Engine) -> Result<String, ResolverError> {
@@ -142,19 +200,28 @@ impl Resolver {
             },
             None => {
                 let err_detail = ErrorDetail::UnknownEngineError;
-                let err = ResolverError::new(err_detail);
-                return Err(err);
+                let err = ResolverError::for_engine(err_detail, e.clone());
+                Err(err)
             },
         }
     }
+
+    /// Returns the [source::Engine]s with a registered [ResolverItem], in sorted order.
+    pub fn engines(&self) -> Vec<&source::Engine> {
+        let mut v: Vec<&source::Engine> = self.resolvers.keys().collect();
+        v.sort();
+        v
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use hex;
     use super::{
+        ErrorDetail,
         Resolver,
         ResolverItem,
+        SimpleResolverItem,
     };
     use crate::source;
     use crate::mock::{TestResolverItem};
@@ -181,4 +248,20 @@ mod tests {
         ri_returned = resolver.pointer_for(&engine_string_two).unwrap();
         assert_eq!(hex::encode(ri_orig_two), ri_returned);
     }
+
+    #[test]
+    fn test_resolver_errors() {
+        let mut resolver: Resolver = Resolver::new();
+        resolver.add("one".to_string(), Box::new(TestResolverItem{key: vec![1]})).unwrap();
+
+        let e = resolver.add("one".to_string(), Box::new(TestResolverItem{key: vec![2]})).unwrap_err();
+        assert_eq!(e.to_string(), "engine 'one' already exists in resolver");
+
+        let e = resolver.pointer_for(&"two".to_string()).unwrap_err();
+        assert_eq!(e.detail(), &ErrorDetail::UnknownEngineError);
+        assert_eq!(e.to_string(), "no resolver entry for engine 'two'");
+
+        assert!(SimpleResolverItem::try_new("deadbeef".to_string()).is_ok());
+        assert!(SimpleResolverItem::try_new("xyzzy".to_string()).is_err());
+    }
 }
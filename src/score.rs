@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+
+use log::debug;
+
+use crate::control::Controller;
+use crate::endpoint::Endpoint;
+use crate::stats::{
+    Outcome,
+    Record,
+};
+
+/// Default number of milliseconds added to the score of an endpoint for a failure rate of 100%.
+pub const DEFAULT_FAILURE_PENALTY: u64 = 5000;
+
+/// Request history of a single [Endpoint].
+#[derive(Debug, Clone, Default)]
+pub struct Score {
+    pub successes: u64,
+    pub failures: u64,
+    /// Sum of the total request time of all successful requests, in milliseconds.
+    pub latency: u64,
+}
+
+impl Score {
+    /// Mean total request time of successful requests, in milliseconds.
+    pub fn mean_latency(&self) -> Option<u64> {
+        match self.successes {
+            0 => None,
+            x => Some(self.latency / x),
+        }
+    }
+
+    /// Calculate the score from the history, where lower is better.
+    ///
+    /// The score is the mean latency, plus the failure rate multiplied by the penalty. An
+    /// endpoint that never succeeded counts the penalty as its latency.
+    pub fn value(&self, failure_penalty: u64) -> u64 {
+        let requests = self.successes + self.failures;
+        let latency = self.mean_latency().unwrap_or(failure_penalty);
+        match requests {
+            0 => latency,
+            x => latency + (failure_penalty * self.failures) / x,
+        }
+    }
+}
+
+/// [Scores] rates the endpoints of a [Controller] by their historical latency and failure
+/// rate, as measured in stats [Record]s.
+///
+/// Records are matched to endpoints by URL. Cancelled requests are not counted, since they say
+/// nothing about the endpoint.
+///
+/// The scores can be applied to a [Controller] with [Controller::reorder].
+pub struct Scores {
+    endpoints: HashMap<String, Score>,
+    pub failure_penalty: u64,
+}
+
+fn endpoint_matches(endpoint_url: &str, url: &str) -> bool {
+    if !url.starts_with(endpoint_url) {
+        return false;
+    }
+    endpoint_url.ends_with('/') || url.len() == endpoint_url.len() || url[endpoint_url.len()..].starts_with('/')
+}
+
+impl Scores {
+    pub fn new(ctrl: &Controller, records: &[Record]) -> Scores {
+        let mut scores = Scores {
+            endpoints: HashMap::new(),
+            failure_penalty: DEFAULT_FAILURE_PENALTY,
+        };
+        let endpoint_urls: Vec<&str> = ctrl.sources.iter()
+            .flat_map(|s| s.endpoints.iter())
+            .map(|e| e.url.as_str())
+            .collect();
+
+        for record in records {
+            let endpoint_url = endpoint_urls.iter()
+                .filter(|v| endpoint_matches(v, &record.url))
+                .max_by_key(|v| v.len());
+            let endpoint_url = match endpoint_url {
+                Some(v) => v,
+                None => {
+                    debug!("no endpoint for stats record {}", record.url);
+                    continue;
+                },
+            };
+            if record.outcome == Outcome::Cancelled {
+                continue;
+            }
+            let score = scores.endpoints.entry(endpoint_url.to_string()).or_default();
+            match &record.outcome {
+                Outcome::Won | Outcome::Completed => {
+                    score.successes += 1;
+                    score.latency += record.total;
+                },
+                _ => {
+                    score.failures += 1;
+                },
+            };
+        }
+        scores
+    }
+
+    /// The history of the [Endpoint], if any counted records were found for it.
+    pub fn score_for(&self, endpoint: &Endpoint) -> Option<&Score> {
+        self.endpoints.get(endpoint.url.as_str())
+    }
+
+    /// The score of the [Endpoint], where lower is better.
+    ///
+    /// Returns `None` if no counted records were found for the endpoint.
+    pub fn value(&self, endpoint: &Endpoint) -> Option<u64> {
+        self.score_for(endpoint).map(|v| v.value(self.failure_penalty))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Scores;
+    use crate::control::Controller;
+    use crate::endpoint::Endpoint;
+    use crate::source::Source;
+    use crate::stats::{
+        Outcome,
+        Record,
+    };
+    use crate::timing::Scheduler;
+
+    fn record(url: &str, total: u64, outcome: Outcome) -> Record {
+        Record {
+            engine: "foo".to_string(),
+            url: url.to_string(),
+            offset: 0,
+            started: 0,
+            first_byte: None,
+            total,
+            bytes: 0,
+            outcome,
+        }
+    }
+
+    fn controller() -> Controller {
+        let mut source_foo = Source::new("foo".to_string());
+        source_foo.endpoints.push(Endpoint::new("https://one.foo.com", None));
+        source_foo.endpoints.push(Endpoint::new("https://two.foo.com/foo", None));
+        source_foo.endpoints.push(Endpoint::new("https://three.foo.com", None));
+        source_foo.timing = Some(Scheduler {
+            delay: 20,
+            timeout: 0,
+        });
+        let mut source_bar = Source::new("bar".to_string());
+        source_bar.endpoints.push(Endpoint::new("https://only.bar.com", None));
+
+        let mut ctrl = Controller::new(Scheduler {
+            delay: 100,
+            timeout: 0,
+        });
+        ctrl.add(source_foo);
+        ctrl.add(source_bar);
+        ctrl
+    }
+
+    #[test]
+    fn test_scores() {
+        let ctrl = controller();
+        let records = vec![
+            record("https://one.foo.com/deadbeef", 400, Outcome::Won),
+            record("https://one.foo.com/deadbeef", 200, Outcome::Completed),
+            record("https://two.foo.com/foo/deadbeef", 10, Outcome::Won),
+            record("https://two.foo.com/foo/deadbeef", 0, Outcome::Timeout),
+            record("https://two.foo.com/foobar/deadbeef", 0, Outcome::Timeout),
+            record("https://only.bar.com/beeffeed", 0, Outcome::Cancelled),
+        ];
+        let scores = Scores::new(&ctrl, &records);
+
+        let endpoints = &ctrl.sources[0].endpoints;
+        assert_eq!(scores.value(&endpoints[0]), Some(300));
+        assert_eq!(scores.value(&endpoints[1]), Some(2510));
+        assert_eq!(scores.score_for(&endpoints[1]).unwrap().failures, 1);
+        assert_eq!(scores.value(&endpoints[2]), None);
+        assert_eq!(scores.value(&ctrl.sources[1].endpoints[0]), None);
+        assert!(scores.score_for(&ctrl.sources[1].endpoints[0]).is_none());
+    }
+
+    #[test]
+    fn test_reorder() {
+        let mut ctrl = controller();
+        let records = vec![
+            record("https://one.foo.com/deadbeef", 400, Outcome::Won),
+            record("https://two.foo.com/foo/deadbeef", 10, Outcome::Won),
+            record("https://two.foo.com/foo/deadbeef", 10, Outcome::Completed),
+            record("https://only.bar.com/beeffeed", 5, Outcome::Won),
+        ];
+        let scores = Scores::new(&ctrl, &records);
+        ctrl.reorder(&scores);
+
+        assert_eq!(ctrl.sources[0].engine, "bar");
+        assert_eq!(ctrl.sources[1].engine, "foo");
+        assert_eq!(ctrl.offsets, vec![0, 100]);
+        let urls: Vec<&str> = ctrl.sources[1].endpoints.iter().map(|e| e.url.as_str()).collect();
+        assert_eq!(urls, vec!["https://two.foo.com/foo", "https://one.foo.com/", "https://three.foo.com/"]);
+    }
+}
@@ -0,0 +1,142 @@
+use std::collections::BTreeMap;
+
+use serde::{
+    de,
+    ser::SerializeStruct,
+    Deserialize,
+    Deserializer,
+    Serialize,
+    Serializer,
+};
+
+use crate::control::Controller;
+use crate::control::graph::Stagger;
+use crate::endpoint::Endpoint;
+use crate::resolver::{
+    Resolver,
+    SimpleResolverItem,
+};
+use crate::source::Source;
+use crate::timing::Scheduler;
+
+/// Serializes a list of binary values as a list of hex strings.
+pub(crate) mod hex_list {
+    use serde::{
+        de,
+        Deserialize,
+        Deserializer,
+        Serializer,
+    };
+
+    pub fn serialize<S: Serializer>(v: &[Vec<u8>], s: S) -> Result<S::Ok, S::Error> {
+        s.collect_seq(v.iter().map(hex::encode))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<Vec<u8>>, D::Error> {
+        let v: Vec<String> = Vec::deserialize(d)?;
+        v.iter().map(|x| hex::decode(x).map_err(de::Error::custom)).collect()
+    }
+}
+
+impl<'a> Serialize for Endpoint<'a> {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        let mut st = s.serialize_struct("Endpoint", 1)?;
+        match &self.template {
+            Some(v) => st.serialize_field("url", &v.to_string())?,
+            None => st.serialize_field("url", self.url.as_str())?,
+        };
+        st.end()
+    }
+}
+
+#[derive(Deserialize)]
+struct EndpointConfig {
+    url: String,
+}
+
+/// The validator cannot be deserialized, so the endpoint uses the
+/// [NoopValidator](crate::validator::NoopValidator).
+impl<'de, 'a> Deserialize<'de> for Endpoint<'a> {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Endpoint<'a>, D::Error> {
+        let v = EndpointConfig::deserialize(d)?;
+        Endpoint::try_new(&v.url, None).map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for Controller {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        let stagger = match self.stagger {
+            Stagger::None => None,
+            Stagger::Spacing(x) => Some(x),
+        };
+        let mut st = s.serialize_struct("Controller", 4)?;
+        st.serialize_field("delay", &self.timing().delay)?;
+        st.serialize_field("timeout", &self.timing().timeout)?;
+        match stagger {
+            Some(x) => st.serialize_field("stagger", &x)?,
+            None => st.skip_field("stagger")?,
+        };
+        st.serialize_field("sources", &self.sources)?;
+        st.end()
+    }
+}
+
+#[derive(Deserialize)]
+struct ControllerConfig {
+    #[serde(default)]
+    delay: u32,
+    #[serde(default)]
+    timeout: u32,
+    #[serde(default)]
+    stagger: Option<u64>,
+    #[serde(default)]
+    sources: Vec<Source<'static>>,
+}
+
+/// Sources are added with [Controller::try_add]. A source may start after a source listed after
+/// it, as long as the engine exists.
+impl<'de> Deserialize<'de> for Controller {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Controller, D::Error> {
+        let v = ControllerConfig::deserialize(d)?;
+        let mut ctrl = Controller::new(Scheduler {
+            delay: v.delay,
+            timeout: v.timeout,
+        });
+        if let Some(x) = v.stagger {
+            ctrl.stagger = Stagger::Spacing(x);
+        }
+        let mut pending = v.sources;
+        while !pending.is_empty() {
+            let i = pending.iter().position(|s| {
+                match &s.start {
+                    Some(crate::source::Start::After{engine, ..}) => ctrl.sources.iter().any(|v| &v.engine == engine),
+                    _ => true,
+                }
+            }).unwrap_or(0);
+            ctrl.try_add(pending.remove(i)).map_err(de::Error::custom)?;
+        }
+        Ok(ctrl)
+    }
+}
+
+/// Serializes as a map of engines to pointers.
+impl Serialize for Resolver {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.collect_map(self.engines().into_iter().filter_map(|e| {
+            self.pointer_for(e).ok().map(|v| (e, v))
+        }))
+    }
+}
+
+/// Deserializes from a map of engines to hex pointers, using [SimpleResolverItem].
+impl<'de> Deserialize<'de> for Resolver {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Resolver, D::Error> {
+        let v: BTreeMap<String, String> = BTreeMap::deserialize(d)?;
+        let mut resolver = Resolver::new();
+        for (engine, pointer) in v {
+            let item = SimpleResolverItem::try_new(pointer).map_err(de::Error::custom)?;
+            resolver.add(engine, Box::new(item)).map_err(de::Error::custom)?;
+        }
+        Ok(resolver)
+    }
+}
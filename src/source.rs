@@ -11,19 +11,45 @@ use super::endpoint::{Endpoint};
 /// build [Endpoint]  URLs.
 pub type Engine = String;
 
+/// Defines when the first [Endpoint] of a [Source] is requested.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(untagged))]
+pub enum Start {
+    /// Milliseconds after the start of the request graph.
+    Absolute(u32),
+    /// Milliseconds after the first request of the [Source] with the given [Engine].
+    After {
+        #[cfg_attr(feature = "serde", serde(rename = "after"))]
+        engine: Engine,
+        #[cfg_attr(feature = "serde", serde(default))]
+        delay: u32,
+    },
+}
+
 /// [Source] encapsulates one or more endpoints to access content using a particular storage
 /// backend [Engine].
 ///
 /// A [Scheduler] may also be included to define with what delay endpoints should be queried in
-/// sequence. 
+/// sequence. If it is not, the schedule of the [Controller](crate::control::Controller) is used.
+///
+/// The `start` of the source sets the offset of its first request. If it is not set, the offset
+/// follows from the position of the source in the [Controller](crate::control::Controller).
+/// Sources with a higher `priority` are placed before those with a lower one.
 ///
 /// Lastly, an array of public keys or public key digests may be provided to verify the origin of
 /// the content.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Source<'a> {
+    #[cfg_attr(feature = "serde", serde(default, with = "crate::serialize::hex_list", skip_serializing_if = "Vec::is_empty"))]
     pub trusted_keys: Vec<Fingerprint>,
     pub endpoints: Vec<Endpoint<'a>>,
+    #[cfg_attr(feature = "serde", serde(default, rename = "schedule", skip_serializing_if = "Option::is_none"))]
     pub timing: Option<Scheduler>,
     pub engine: Engine,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub start: Option<Start>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub priority: i32,
 }
 
 impl<'a> Source<'a> {
@@ -32,7 +58,9 @@ impl<'a> Source<'a> {
             trusted_keys: vec![],
             endpoints: vec![],
             timing: None,
-            engine: engine,
+            engine,
+            start: None,
+            priority: 0,
         }
     }
 }
@@ -0,0 +1,123 @@
+use std::fmt;
+use std::sync::Mutex;
+
+use crate::exec::RequestError;
+use crate::source::Engine;
+
+/// Stores [Record]s as JSON lines.
+#[cfg(feature = "json")]
+mod json;
+#[cfg(feature = "json")]
+pub use json::{
+    JsonLinesSink,
+    read_json_lines,
+};
+
+/// The final state of a single request measured in a [Record].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    /// The request provided the result of the run.
+    Won,
+    /// The request returned valid content, but did not provide the result of the run.
+    Completed,
+    /// The request was cancelled before it completed.
+    Cancelled,
+    /// The request did not complete before its timeout.
+    Timeout,
+    /// The request returned content that failed validation.
+    Invalid,
+    /// The request failed, with a description of why.
+    Failed(String),
+}
+
+impl Outcome {
+    /// Returns `true` if the request returned valid content.
+    pub fn is_success(&self) -> bool {
+        matches!(self, Outcome::Won | Outcome::Completed)
+    }
+}
+
+impl From<&RequestError> for Outcome {
+    fn from(e: &RequestError) -> Outcome {
+        match e {
+            RequestError::Timeout => Outcome::Timeout,
+            RequestError::Cancelled => Outcome::Cancelled,
+            RequestError::Invalid => Outcome::Invalid,
+            _ => Outcome::Failed(e.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Outcome::Won => write!(f, "won"),
+            Outcome::Completed => write!(f, "completed"),
+            Outcome::Cancelled => write!(f, "cancelled"),
+            Outcome::Timeout => write!(f, "timeout"),
+            Outcome::Invalid => write!(f, "invalid"),
+            Outcome::Failed(_) => write!(f, "failed"),
+        }
+    }
+}
+
+/// Measurements of a single request dispatched by the [Runner](crate::exec::Runner).
+///
+/// All times are in milliseconds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    pub engine: Engine,
+    pub url: String,
+    /// Offset from the start of the run at which the request was scheduled.
+    pub offset: u64,
+    /// Offset from the start of the run at which the request was actually dispatched.
+    pub started: u64,
+    /// Time from dispatch until the first byte of content was received, if reported by the
+    /// adapter.
+    pub first_byte: Option<u64>,
+    /// Time from dispatch until the request completed, failed or was cancelled.
+    pub total: u64,
+    /// Number of bytes of content received.
+    pub bytes: u64,
+    pub outcome: Outcome,
+}
+
+/// A backend storing the [Record]s of the requests made by a [Runner](crate::exec::Runner).
+pub trait StatsSink {
+
+    /// Store a single [Record].
+    ///
+    /// Failing to store the record must not affect the run, so errors should be handled
+    /// within the sink.
+    fn record(&self, record: &Record);
+}
+
+/// Keeps [Record]s in memory.
+pub struct MemorySink {
+    records: Mutex<Vec<Record>>,
+}
+
+impl Default for MemorySink {
+    fn default() -> MemorySink {
+        MemorySink::new()
+    }
+}
+
+impl MemorySink {
+    pub fn new() -> MemorySink {
+        MemorySink {
+            records: Mutex::new(vec![]),
+        }
+    }
+
+    /// Returns a copy of all records stored so far.
+    pub fn records(&self) -> Vec<Record> {
+        self.records.lock().unwrap().clone()
+    }
+}
+
+impl StatsSink for MemorySink {
+    fn record(&self, record: &Record) {
+        self.records.lock().unwrap().push(record.clone());
+    }
+}
@@ -0,0 +1,200 @@
+use std::fs;
+use std::io::{
+    self,
+    BufRead,
+    Write,
+};
+use std::path::Path;
+use std::sync::Mutex;
+
+use log::{
+    error,
+    warn,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use super::{
+    Outcome,
+    Record,
+    StatsSink,
+};
+
+/// The JSON representation of a [Record], with the description of a failure under `error`.
+#[derive(Serialize, Deserialize)]
+struct RecordJson {
+    engine: String,
+    url: String,
+    offset: u64,
+    started: u64,
+    first_byte: Option<u64>,
+    total: u64,
+    bytes: u64,
+    outcome: String,
+    error: Option<String>,
+}
+
+impl Record {
+    /// Render the record as a single line JSON object.
+    pub fn to_json(&self) -> String {
+        let error = match &self.outcome {
+            Outcome::Failed(v) => Some(v.clone()),
+            _ => None,
+        };
+        let v = RecordJson {
+            engine: self.engine.clone(),
+            url: self.url.clone(),
+            offset: self.offset,
+            started: self.started,
+            first_byte: self.first_byte,
+            total: self.total,
+            bytes: self.bytes,
+            outcome: self.outcome.to_string(),
+            error,
+        };
+        serde_json::to_string(&v).unwrap()
+    }
+
+    /// Parse a record from a JSON object as rendered by [Record::to_json].
+    pub fn from_json(s: &str) -> Result<Record, String> {
+        let v: RecordJson = serde_json::from_str(s).map_err(|e| e.to_string())?;
+        let outcome = match v.outcome.as_str() {
+            "won" => Outcome::Won,
+            "completed" => Outcome::Completed,
+            "cancelled" => Outcome::Cancelled,
+            "timeout" => Outcome::Timeout,
+            "invalid" => Outcome::Invalid,
+            "failed" => Outcome::Failed(v.error.unwrap_or_default()),
+            x => {
+                return Err(format!("unknown outcome '{}'", x));
+            },
+        };
+        Ok(Record {
+            engine: v.engine,
+            url: v.url,
+            offset: v.offset,
+            started: v.started,
+            first_byte: v.first_byte,
+            total: v.total,
+            bytes: v.bytes,
+            outcome,
+        })
+    }
+}
+
+/// Read all [Record]s from a file written by [JsonLinesSink].
+///
+/// Lines that cannot be parsed are skipped.
+pub fn read_json_lines(path: &Path) -> Result<Vec<Record>, io::Error> {
+    let f = fs::File::open(path)?;
+    let mut records = vec![];
+    for line in io::BufReader::new(f).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match Record::from_json(&line) {
+            Ok(v) => records.push(v),
+            Err(e) => {
+                warn!("skipping invalid stats record in {:?}: {}", path, e);
+            },
+        };
+    }
+    Ok(records)
+}
+
+/// Appends [Record]s to a file, one JSON object per line.
+pub struct JsonLinesSink {
+    f: Mutex<fs::File>,
+}
+
+impl JsonLinesSink {
+    /// Open the file at the given path for appending, creating it if it does not exist.
+    pub fn new(path: &Path) -> Result<JsonLinesSink, io::Error> {
+        let f = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        Ok(JsonLinesSink {
+            f: Mutex::new(f),
+        })
+    }
+}
+
+impl StatsSink for JsonLinesSink {
+    fn record(&self, record: &Record) {
+        let mut f = self.f.lock().unwrap();
+        if let Err(e) = writeln!(f, "{}", record.to_json()) {
+            error!("could not write stats record for {}: {}", record.url, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    use super::{
+        JsonLinesSink,
+        Outcome,
+        Record,
+        StatsSink,
+        read_json_lines,
+    };
+
+    #[test]
+    fn test_record_json() {
+        let mut record = Record {
+            engine: "foo".to_string(),
+            url: "http://foo.com/deadbeef".to_string(),
+            offset: 200,
+            started: 201,
+            first_byte: Some(13),
+            total: 42,
+            bytes: 3,
+            outcome: Outcome::Won,
+        };
+        assert_eq!(record.to_json(), "{\"engine\":\"foo\",\"url\":\"http://foo.com/deadbeef\",\"offset\":200,\"started\":201,\"first_byte\":13,\"total\":42,\"bytes\":3,\"outcome\":\"won\",\"error\":null}");
+
+        record.first_byte = None;
+        record.outcome = Outcome::Failed("no \"route\"".to_string());
+        assert_eq!(record.to_json(), "{\"engine\":\"foo\",\"url\":\"http://foo.com/deadbeef\",\"offset\":200,\"started\":201,\"first_byte\":null,\"total\":42,\"bytes\":3,\"outcome\":\"failed\",\"error\":\"no \\\"route\\\"\"}");
+        assert_eq!(Record::from_json(&record.to_json()).unwrap(), record);
+
+        record.outcome = Outcome::Timeout;
+        assert_eq!(Record::from_json(&record.to_json()).unwrap(), record);
+
+        record.bytes = u64::MAX;
+        assert_eq!(Record::from_json(&record.to_json()).unwrap(), record);
+
+        let s = record.to_json().replace("deadbeef", "\\uD83D\\uDE00");
+        assert_eq!(Record::from_json(&s).unwrap().url, "http://foo.com/\u{1F600}");
+    }
+
+    #[test]
+    fn test_json_lines_sink() {
+        let path = env::temp_dir().join(format!("fadfada-stats-{}.jsonl", process::id()));
+        let record = Record {
+            engine: "foo".to_string(),
+            url: "http://foo.com/deadbeef".to_string(),
+            offset: 0,
+            started: 1,
+            first_byte: None,
+            total: 42,
+            bytes: 0,
+            outcome: Outcome::Failed("refused".to_string()),
+        };
+        let sink = JsonLinesSink::new(&path).unwrap();
+        sink.record(&record);
+        sink.record(&record);
+
+        let records = read_json_lines(&path).unwrap();
+        assert_eq!(records, vec![record.clone(), record]);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,7 +1,10 @@
 /// [Scheduler] is used with [Controller](crate::control::Controller) and [Source](crate::source::Source) to define query offsets and timeouts on a
 /// per-session and per-source basis.
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Scheduler {
+    #[cfg_attr(feature = "serde", serde(default))]
     pub delay: u32,
+    #[cfg_attr(feature = "serde", serde(default))]
     pub timeout: u32,
 }
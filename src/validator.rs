@@ -1,9 +1,16 @@
+use std::collections::HashMap;
+
 use log::debug;
 
 use super::resolver::{
     Digest,
     Signature,
 };
+use super::keystore::{
+    Key,
+    Keystore,
+    Fingerprint,
+};
 
 /// The [Validator] is optionally used with a result from a [Source](crate:.source::Source) to verify the origin of
 /// content.
@@ -11,6 +18,98 @@ pub trait Validator {
 
     /// Returns `true` if [Signature] can be verified against the [Digest] of content.
     fn verify(&self, digest: &Digest, content: Option<&Vec<u8>>, signature: Option<&Signature>) -> bool;
+
+    /// Same as [Validator::verify], but only accepting signatures made by the keys in
+    /// `trusted_keys`.
+    ///
+    /// This is used to enforce the `trusted_keys` of a [Source](crate::source::Source). The
+    /// default implementation ignores the keys, which is correct for validators that do not
+    /// check signatures.
+    fn verify_trusted(&self, digest: &Digest, content: Option<&Vec<u8>>, signature: Option<&Signature>, _trusted_keys: &[Fingerprint]) -> bool {
+        self.verify(digest, content, signature)
+    }
+}
+
+/// Checks a [Signature] over a [Digest] with a public [Key] of a particular signature scheme.
+pub trait SignatureVerifier {
+
+    /// Returns `true` if the [Signature] over the [Digest] was made by the private key of the
+    /// public [Key].
+    fn verify_signature(&self, key: &Key, digest: &Digest, signature: &Signature) -> bool;
+}
+
+/// [SignatureValidator] accepts content if its signature was made by a key in a [Keystore].
+///
+/// When used with [Validator::verify_trusted], only the keys with the given fingerprints are
+/// accepted. If the list of trusted keys is empty, the content is rejected, unless `any_key` is
+/// set, in which case all keys in the [Keystore] are accepted. [Validator::verify] checks the
+/// signature with an empty list of trusted keys.
+///
+/// Content without a signature is always rejected.
+///
+/// The signature is made over the digest, which comes from the resolver rather than from the
+/// endpoint. The `content` validator checks that the content returned by the endpoint matches
+/// that digest, e.g. the [Sha256ImmutableValidator](crate::web2::Sha256ImmutableValidator) for
+/// sha256 digests. Without content to check, the content is rejected.
+pub struct SignatureValidator<'a> {
+    pub keystore: &'a dyn Keystore,
+    pub scheme: &'a dyn SignatureVerifier,
+    pub content: &'a dyn Validator,
+    pub any_key: bool,
+}
+
+impl<'a> SignatureValidator<'a> {
+    pub fn new(keystore: &'a dyn Keystore, scheme: &'a dyn SignatureVerifier, content: &'a dyn Validator) -> SignatureValidator<'a> {
+        SignatureValidator {
+            keystore,
+            scheme,
+            content,
+            any_key: false,
+        }
+    }
+}
+
+impl<'a> Validator for SignatureValidator<'a> {
+    fn verify(&self, digest: &Digest, content: Option<&Vec<u8>>, signature: Option<&Signature>) -> bool {
+        self.verify_trusted(digest, content, signature, &[])
+    }
+
+    fn verify_trusted(&self, digest: &Digest, content: Option<&Vec<u8>>, signature: Option<&Signature>, trusted_keys: &[Fingerprint]) -> bool {
+        if content.is_none() {
+            debug!("no content for digest {:?}", digest);
+            return false;
+        }
+        if !self.content.verify(digest, content, None) {
+            debug!("content does not match digest {:?}", digest);
+            return false;
+        }
+        let signature = match signature {
+            Some(v) => v,
+            None => {
+                debug!("no signature for digest {:?}", digest);
+                return false;
+            },
+        };
+        let fingerprints = match trusted_keys.len() {
+            0 if self.any_key => self.keystore.fingerprints(),
+            0 => {
+                debug!("no trusted keys for digest {:?}", digest);
+                return false;
+            },
+            _ => trusted_keys.to_vec(),
+        };
+        let r = fingerprints.iter().any(|fp| {
+            match self.keystore.get(fp) {
+                Some(key) => self.scheme.verify_signature(key, digest, signature),
+                None => {
+                    debug!("trusted key {} not in keystore", hex::encode(fp));
+                    false
+                },
+            }
+        });
+        debug!("verify signature for digest {:?}: {}", digest, r);
+        r
+    }
 }
 
 /// The default value of [Validator], which performs no validation.
@@ -25,3 +124,120 @@ impl Validator for NoopValidator {
 }
 
 pub const NOOPVALIDATOR: NoopValidator = NoopValidator{};
+
+/// Rejects all content.
+///
+/// Used in place of a validator that is named in configuration but could not be found, so that
+/// content from the endpoint is never accepted unvalidated.
+pub struct RejectValidator {
+}
+
+impl Validator for RejectValidator {
+    fn verify(&self, digest: &Digest, _content: Option<&Vec<u8>>, _signature: Option<&Signature>) -> bool {
+        debug!("reject validator verify digest {:?}", digest);
+        false
+    }
+}
+
+pub const REJECTVALIDATOR: RejectValidator = RejectValidator{};
+
+/// Maps names to [Validator]s, so that configuration documents can refer to them by name.
+///
+/// A new registry contains the [NoopValidator] as `noop` and, with the `web2` feature, the
+/// [Sha256ImmutableValidator](crate::web2::Sha256ImmutableValidator) as `sha256`. Other
+/// validators, like a [SignatureValidator] over a particular [Keystore], must be added by the
+/// application.
+///
+/// Signature validators cannot be defined in configuration documents: the registry only maps
+/// names, and the keys a source accepts are referenced by fingerprint in its `trusted_keys`.
+/// Registering a [SignatureValidator] under a name, e.g. `ed25519`, lets endpoints refer to it.
+pub struct ValidatorRegistry {
+    validators: HashMap<String, &'static dyn Validator>,
+}
+
+impl Default for ValidatorRegistry {
+    fn default() -> ValidatorRegistry {
+        ValidatorRegistry::new()
+    }
+}
+
+impl ValidatorRegistry {
+    pub fn new() -> ValidatorRegistry {
+        let mut registry = ValidatorRegistry {
+            validators: HashMap::new(),
+        };
+        registry.add("noop", &NOOPVALIDATOR);
+        #[cfg(feature = "web2")]
+        registry.add("sha256", &crate::web2::Sha256ImmutableValidator{});
+        registry
+    }
+
+    /// Register a [Validator] by name, replacing any validator already registered with that name.
+    pub fn add(&mut self, name: &str, validator: &'static dyn Validator) {
+        debug!("added validator {}", name);
+        self.validators.insert(name.to_string(), validator);
+    }
+
+    /// Retrieve the [Validator] registered with the name.
+    pub fn get(&self, name: &str) -> Option<&'static dyn Validator> {
+        self.validators.get(name).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        SignatureValidator,
+        Validator,
+        ValidatorRegistry,
+    };
+    use crate::keystore::{
+        Keystore,
+        MemoryKeystore,
+    };
+    use crate::mock::{
+        TestSignatureVerifier,
+        TestValidator,
+    };
+
+    #[test]
+    fn test_signature_validator() {
+        let mut keystore = MemoryKeystore::new();
+        let fp_foo = keystore.add(vec![1]);
+        let fp_bar = keystore.add(vec![2]);
+        let scheme = TestSignatureVerifier{};
+        let content = TestValidator{};
+        let mut v = SignatureValidator::new(&keystore, &scheme, &content);
+
+        let digest = vec![4, 5, 6];
+        let signature_foo = vec![1, 4, 5, 6];
+        let signature_baz = vec![3, 4, 5, 6];
+
+        let trusted_foo = [fp_foo];
+        assert!(v.verify_trusted(&digest, Some(&digest), Some(&signature_foo), &trusted_foo));
+        assert!(!v.verify_trusted(&digest, Some(&digest), None, &trusted_foo));
+        assert!(!v.verify_trusted(&digest, Some(&digest), Some(&signature_foo), &[fp_bar]));
+        assert!(!v.verify_trusted(&digest, Some(&digest), Some(&signature_foo), &[vec![3]]));
+
+        assert!(!v.verify_trusted(&digest, Some(&vec![6, 6, 6]), Some(&signature_foo), &trusted_foo));
+        assert!(!v.verify_trusted(&digest, None, Some(&signature_foo), &trusted_foo));
+
+        assert!(!v.verify_trusted(&digest, Some(&digest), Some(&signature_foo), &[]));
+        assert!(!v.verify(&digest, Some(&digest), Some(&signature_foo)));
+        v.any_key = true;
+        assert!(v.verify(&digest, Some(&digest), Some(&signature_foo)));
+        assert!(!v.verify(&digest, Some(&digest), Some(&signature_baz)));
+        assert!(!v.verify(&digest, Some(&digest), None));
+    }
+
+    #[test]
+    fn test_validator_registry() {
+        let mut registry = ValidatorRegistry::new();
+        assert!(registry.get("noop").unwrap().verify(&vec![1], Some(&vec![2]), None));
+        assert!(registry.get("foo").is_none());
+
+        registry.add("foo", &TestValidator{});
+        assert!(!registry.get("foo").unwrap().verify(&vec![1], Some(&vec![2]), None));
+        assert!(registry.get("foo").unwrap().verify(&vec![1], Some(&vec![1]), None));
+    }
+}
@@ -15,7 +15,7 @@ use crate::validator::{
 };
 
 pub fn engine() -> Engine {
-    return "web2".to_string();
+    "web2".to_string()
 }
 
 /// The default web2 implementation of `ResolverItem` is the `Sha256ImmutableResolver`. This
@@ -26,27 +26,30 @@ pub struct Sha256ImmutableResolverItem {
     pub key: Vec<u8>,
     /// The verbatim content of the resource.
     pub content: Option<Vec<u8>>,
+    /// Detached signature over the digest of the resource, if the resource is signed.
+    pub signature: Option<Signature>,
 }
 
 impl Sha256ImmutableResolverItem {
-    pub fn new(key: &Vec<u8>, content: Option<Vec<u8>>) -> Self {
+    pub fn new(key: &[u8], content: Option<Vec<u8>>) -> Self {
         Sha256ImmutableResolverItem{
-            key: key.clone(),
-            content: content,
+            key: key.to_vec(),
+            content,
+            signature: None,
         }
     }
 }
 
 impl ResolverItem for Sha256ImmutableResolverItem {
     fn digest(&self) -> &ResolverDigest {
-        return &self.key;
+        &self.key
     }
-    fn signature(&self) -> Result<ResolverDigest, ResolverError> {
-        Ok(Vec::new())     
+    fn signature(&self) -> Result<Signature, ResolverError> {
+        Ok(self.signature.clone().unwrap_or_default())
     }
     fn pointer(&self) -> String {
         let v = &self.key;
-        return hex::encode(v);
+        hex::encode(v)
     }
 }
 
@@ -25,14 +25,41 @@
 //! ```
 //!
 //! This results in requests for each engine fired 200 ms apart, where the request for the "bar"
-//! engine is fired slightly after [^persched] the first request for the "foo" engine, resulting in something
-//! like:
+//! engine is fired together with the first request for the "foo" engine, resulting in:
 //! 
 //! * after 0 ms: http://one.foo.com
-//! * after 1 ms: http://only.bar.com
+//! * after 0 ms: http://only.bar.com
 //! * after 200 ms: http://two.foo.com
 //!
-//! [^persched]: The ability to define per-engine offsets and schedules is intended but not yet implemented.
+//! Requests at the same offset can be spaced apart by adding `stagger: 1` to the document, which
+//! would instead fire the request for "bar" after 1 ms.
+//!
+//! Each source may override the schedule with a `schedule` entry of its own, and define when its
+//! first request is made with `start`, either as an absolute offset or relative to the first
+//! request of another engine. Sources with a higher `priority` are placed first.
+//!
+//! ``` ignore,
+//! sources:
+//!   - engine: swarm
+//!     schedule:
+//!       delay: 500
+//!     endpoints:
+//!       - url: http://one.swarm.org
+//!       - url: http://two.swarm.org
+//!   - engine: web2
+//!     schedule:
+//!       delay: 200
+//!     start:
+//!       after: swarm
+//!       delay: 400
+//!     endpoints:
+//!       - url: http://one.foo.com
+//!       - url: http://two.foo.com
+//! ```
+//!
+//! Here the first web2 request is made 400 ms after the first swarm request, followed by the second
+//! swarm request at 500 ms and the second web2 request at 600 ms. An absolute offset is given as
+//! `start: 400`.
 //!
 //! # content
 //!
@@ -49,17 +76,99 @@
 //! the previous paragraph, the resulting query graph becomes:
 //!
 //! * after 0 ms: http://one.foo.com/deadbeef
-//! * after 1 ms: http://only.bar.com/beeffeed
+//! * after 0 ms: http://only.bar.com/beeffeed
 //! * after 200 ms: http://two.foo.com/deadbeef
+//!
+//! # templates
+//!
+//! Instead of being appended to the url, the reference can be placed anywhere after the host
+//! with a `{pointer}` placeholder, or part of it with `{pointer[0:2]}`. This suits mirrors that
+//! shard their content by the first characters of the reference:
+//!
+//! ``` ignore,
+//! sources:
+//!   - engine: foo
+//!     endpoints:
+//!       - url: https://${MIRROR_HOST}/{region}/{pointer[0:2]}/{pointer}
+//! ```
+//!
+//! With the [Loader](loader::Loader), `${MIRROR_HOST}` is replaced with the environment
+//! variable, and `{region}` with the entry in its `variables`, so that the same documents can be
+//! used for different environments. With `MIRROR_HOST` set to `mirror.example` and `region` to
+//! `eu`, the request for "foo" is made to `https://mirror.example/eu/de/deadbeef`. The [FromYaml]
+//! implementations do not substitute variables, and reject urls that contain them.
+//!
+//! # files
+//!
+//! The `control` and `content` documents may be kept in one file, separated by `---`. With the
+//! [Loader](loader::Loader), configuration can also be split across a directory of files, for
+//! example one per engine, and sources can `include` lists of endpoints shared between them:
+//!
+//! ``` ignore,
+//! engine: foo
+//! endpoints:
+//!   - include: shared/foo.yaml
+//!   - url: http://three.foo.com
+//! ---
+//! foo: deadbeef
+//! ```
+//!
+//! # validation
+//!
+//! The [FromYaml] implementations stop at the first problem and ignore keys they do not know.
+//! The `validator` of an endpoint is looked up among the validators of a new
+//! [ValidatorRegistry](crate::validator::ValidatorRegistry), such as `noop`. Endpoints naming any
+//! other validator get the [RejectValidator](crate::validator::RejectValidator), so that their
+//! content is never accepted unvalidated.
+//! To check documents before deploying them, load them with the [Loader](loader::Loader)
+//! instead, which rejects unknown keys and reports every problem with its path and position:
+//!
+//! ``` text
+//! sources[1].endpoints[0].url: invalid url 'one.foo.com': relative URL without a base at line 7 column 9
+//! ```
+//!
+//! The [Loader](loader::Loader) also attaches the validators named by the `validator` key of
+//! endpoints, looking them up in its [ValidatorRegistry](crate::validator::ValidatorRegistry),
+//! to which the application can add its own.
+//! Signatures are checked against the keys listed in the `trusted_keys` of the source, given as
+//! hex fingerprints:
+//!
+//! ``` ignore,
+//! sources:
+//!   - engine: foo
+//!     trusted_keys:
+//!       - "deadbeef"
+//!     endpoints:
+//!       - url: http://one.foo.com
+//!         validator: sha256
+//! ```
+
+/// Strict loading of yaml documents, reporting every problem found.
+pub mod loader;
 
 use crate::control::Controller;
+use crate::control::graph::Stagger;
+use crate::error::Error;
 use crate::timing::Scheduler;
-use crate::source::Source;
+use crate::source::{
+    Source,
+    Start,
+};
 use crate::endpoint::Endpoint;
+use crate::manifest::{
+    Manifest,
+    Resource,
+};
 use crate::resolver::{
     Resolver,
     SimpleResolverItem,
 };
+use crate::validator::{
+    ValidatorRegistry,
+    REJECTVALIDATOR,
+};
+
+use log::warn;
 
 use yaml_rust::{
     Yaml,
@@ -69,17 +178,58 @@ use yaml_rust::yaml::{
     Hash,
 };
 
+/// Constructs an object from a YAML mapping.
 pub trait FromYaml<T> {
-    fn from_yaml(y: &Hash, schedule: Option<&Scheduler>) -> T;
+
+    /// Construct the object from the mapping.
+    ///
+    /// Panics if the mapping is invalid; see [FromYaml::try_from_yaml].
+    fn from_yaml(y: &Hash, schedule: Option<&Scheduler>) -> T {
+        Self::try_from_yaml(y, schedule).unwrap()
+    }
+
+    /// Construct the object from the mapping.
+    ///
+    /// Will error with [Error::Schema] if the mapping does not have the expected structure.
+    fn try_from_yaml(y: &Hash, schedule: Option<&Scheduler>) -> Result<T, Error>;
+}
+
+fn get_str<'y>(y: &'y Hash, k: &str) -> Result<&'y str, Error> {
+    match y.get(&Yaml::from_str(k)) {
+        Some(v) => v.as_str().ok_or_else(|| Error::Schema(format!("'{}' must be a string", k))),
+        None => Err(Error::Schema(format!("missing '{}'", k))),
+    }
+}
+
+fn get_u32(y: &Hash, k: &str) -> Result<Option<u32>, Error> {
+    match y.get(&Yaml::from_str(k)) {
+        Some(v) => match v.as_i64() {
+            Some(x) if x >= 0 && x <= u32::MAX as i64 => Ok(Some(x as u32)),
+            _ => Err(Error::Schema(format!("'{}' must be an integer between 0 and {}", k, u32::MAX))),
+        },
+        None => Ok(None),
+    }
+}
+
+fn as_hash<'y>(y: &'y Yaml, k: &str) -> Result<&'y Hash, Error> {
+    y.as_hash().ok_or_else(|| Error::Schema(format!("'{}' must be a mapping", k)))
+}
+
+fn get_vec<'y>(y: &'y Hash, k: &str) -> Result<Option<&'y Vec<Yaml>>, Error> {
+    match y.get(&Yaml::from_str(k)) {
+        Some(v) => match v.as_vec() {
+            Some(x) => Ok(Some(x)),
+            None => Err(Error::Schema(format!("'{}' must be a list", k))),
+        },
+        None => Ok(None),
+    }
 }
 
 impl FromYaml<Scheduler> for Scheduler {
-    fn from_yaml(y: &Hash, schedule_defaults: Option<&Scheduler>) -> Scheduler {
+    fn try_from_yaml(y: &Hash, schedule_defaults: Option<&Scheduler>) -> Result<Scheduler, Error> {
         let mut schedule: Scheduler; 
-        let mut k = Yaml::from_str("delay");
-        let delay = y.get(&k); //.unwrap().as_i64().unwrap();
-        k = Yaml::from_str("timeout");
-        let timeout = y.get(&k); //.unwrap().as_i64().unwrap();
+        let delay = get_u32(y, "delay")?;
+        let timeout = get_u32(y, "timeout")?;
 
         match schedule_defaults {
             Some(v) => {
@@ -93,123 +243,260 @@ impl FromYaml<Scheduler> for Scheduler {
             },
         };
 
-        match delay {
-            Some(v) => {
-                schedule.delay = v.as_i64().unwrap() as u32;
-            },
-            _ => {},
+        if let Some(v) = delay {
+            schedule.delay = v;
         };
 
-        match timeout {
-            Some(v) => {
-                schedule.timeout = v.as_i64().unwrap() as u32;
-            },
-            _ => {},
+        if let Some(v) = timeout {
+            schedule.timeout = v;
         };
 
-        return schedule;
+        Ok(schedule)
     }
 }
 
 impl<'a> FromYaml<Endpoint<'a>> for Endpoint<'a> {
-    fn from_yaml(y: &Hash, _schedule_default: Option<&Scheduler>) -> Endpoint<'a> {
-        let k = Yaml::from_str("url");
-        let url_string = y.get(&k).unwrap().as_str().unwrap();
-        Endpoint::new(
-            url_string,
-            None,
-            )
+    fn try_from_yaml(y: &Hash, _schedule_default: Option<&Scheduler>) -> Result<Endpoint<'a>, Error> {
+        endpoint_from_yaml(y, &ValidatorRegistry::new())
     }
 }
 
-impl<'a> FromYaml<Source<'a>> for Source<'a> {
-    fn from_yaml(y: &Hash, schedule_default: Option<&Scheduler>) -> Source<'a> {
-        let mut k = Yaml::from_str("engine");
-        let engine = y.get(&k).unwrap().as_str().unwrap();
-        let mut source = Source::new(engine.to_string());
-        k = Yaml::from_str("endpoints");
-        let endpoints = y.get(&k).unwrap().as_vec().unwrap();
-        for endpoint_entry in endpoints {
-            let endpoint_y = endpoint_entry.as_hash().unwrap();
-            let endpoint = Endpoint::from_yaml(endpoint_y, schedule_default);
-            source.endpoints.push(endpoint);
-        }
-
-        k = Yaml::from_str("schedule");
-        match y.get(&k) {
-            Some(schedule_entry) => {
-                let schedule_y = schedule_entry.as_hash().unwrap();
-                source.timing = Some(Scheduler::from_yaml(schedule_y, None));
-            }, 
-            _ => {
-                match schedule_default {
-                    Some(v) => {
-                        source.timing = Some(v.clone());
-                    },
-                    None => {
-                        let scheduler_fallback = Scheduler {
-                            delay: 0,
-                            timeout: 0,
-                        };
-                        source.timing = Some(scheduler_fallback);
-                    },
-                };
+/// Construct an [Endpoint], looking up its `validator` in `validators`.
+fn endpoint_from_yaml<'a>(y: &Hash, validators: &ValidatorRegistry) -> Result<Endpoint<'a>, Error> {
+    let url_string = get_str(y, "url")?;
+    let mut endpoint = Endpoint::try_new(
+        url_string,
+        None,
+        )?;
+    if y.contains_key(&Yaml::from_str("validator")) {
+        let name = get_str(y, "validator")?;
+        endpoint.validator = match validators.get(name) {
+            Some(v) => v,
+            None => {
+                warn!("unknown validator '{}' for {}, all content from it will be rejected", name, url_string);
+                &REJECTVALIDATOR
             },
         };
-        return source;
     }
+    Ok(endpoint)
+}
+
+impl<'a> FromYaml<Source<'a>> for Source<'a> {
+    fn try_from_yaml(y: &Hash, schedule_default: Option<&Scheduler>) -> Result<Source<'a>, Error> {
+        source_from_yaml(y, schedule_default, &ValidatorRegistry::new())
+    }
+}
+
+/// Construct a [Source], looking up the `validator` of its endpoints in `validators`.
+fn source_from_yaml<'a>(y: &Hash, schedule_default: Option<&Scheduler>, validators: &ValidatorRegistry) -> Result<Source<'a>, Error> {
+    let engine = get_str(y, "engine")?;
+    let mut source = Source::new(engine.to_string());
+    let endpoints = match get_vec(y, "endpoints")? {
+        Some(v) => v,
+        None => {
+            return Err(Error::Schema("missing 'endpoints'".to_string()));
+        },
+    };
+    for endpoint_entry in endpoints {
+        let endpoint_y = as_hash(endpoint_entry, "endpoints")?;
+        let endpoint = endpoint_from_yaml(endpoint_y, validators)?;
+        source.endpoints.push(endpoint);
+    }
+
+    if let Some(keys) = get_vec(y, "trusted_keys")? {
+        for key_entry in keys {
+            let key = key_entry.as_str().ok_or_else(|| Error::Schema("'trusted_keys' must be a list of strings".to_string()))?;
+            let fp = hex::decode(key).map_err(|e| Error::Decode(format!("invalid hex key '{}': {}", key, e)))?;
+            source.trusted_keys.push(fp);
+        }
+    }
+
+    match y.get(&Yaml::from_str("start")) {
+        Some(Yaml::Integer(_)) => {
+            source.start = get_u32(y, "start")?.map(Start::Absolute);
+        },
+        Some(start_entry) => {
+            let start_y = as_hash(start_entry, "start")?;
+            source.start = Some(Start::After {
+                engine: get_str(start_y, "after")?.to_string(),
+                delay: get_u32(start_y, "delay")?.unwrap_or(0),
+            });
+        },
+        None => {},
+    };
+
+    if let Some(v) = y.get(&Yaml::from_str("priority")) {
+        source.priority = v.as_i64()
+            .and_then(|x| std::convert::TryFrom::try_from(x).ok())
+            .ok_or_else(|| Error::Schema("'priority' must be an integer".to_string()))?;
+    };
+
+    let k = Yaml::from_str("schedule");
+    match y.get(&k) {
+        Some(schedule_entry) => {
+            let schedule_y = as_hash(schedule_entry, "schedule")?;
+            source.timing = Some(Scheduler::try_from_yaml(schedule_y, None)?);
+        }, 
+        _ => {
+            match schedule_default {
+                Some(v) => {
+                    source.timing = Some(v.clone());
+                },
+                None => {
+                    let scheduler_fallback = Scheduler {
+                        delay: 0,
+                        timeout: 0,
+                    };
+                    source.timing = Some(scheduler_fallback);
+                },
+            };
+        },
+    };
+    Ok(source)
 }
 
 impl FromYaml<Controller> for Controller {
-    fn from_yaml(y: &Hash, schedule_default: Option<&Scheduler>) -> Controller {
-        let schedule = Scheduler::from_yaml(y, schedule_default);
-
-        let mut ctrl = Controller::new(schedule.clone()); //.clone());
-
-        let k = Yaml::from_str("sources");
-        match y.get(&k) {
-            Some(sources_entry) => {
-                for source_entry in sources_entry.as_vec().unwrap() {
-                    let source_y = source_entry.as_hash().unwrap();
-                    let source = Source::from_yaml(source_y, Some(&schedule));
-                    ctrl.add(source);
-                }
-            },
-            _ => {},
-        };
+    fn try_from_yaml(y: &Hash, schedule_default: Option<&Scheduler>) -> Result<Controller, Error> {
+        controller_from_yaml(y, schedule_default, &ValidatorRegistry::new())
+    }
+}
+
+/// Construct a [Controller], looking up the `validator` of its endpoints in `validators`.
+pub(crate) fn controller_from_yaml(y: &Hash, schedule_default: Option<&Scheduler>, validators: &ValidatorRegistry) -> Result<Controller, Error> {
+    let schedule = Scheduler::try_from_yaml(y, schedule_default)?;
 
-        return ctrl;
+    let mut ctrl = Controller::new(schedule.clone());
+    if let Some(x) = get_u32(y, "stagger")? {
+        ctrl.stagger = Stagger::Spacing(x as u64);
     }
+
+    if let Some(sources_entry) = get_vec(y, "sources")? {
+        for source_entry in sources_entry {
+            let source_y = as_hash(source_entry, "sources")?;
+            let source = source_from_yaml(source_y, Some(&schedule), validators)?;
+            ctrl.try_add(source)?;
+        }
+    };
+
+    Ok(ctrl)
 }
 
 impl FromYaml<Resolver> for Resolver {
-    fn from_yaml(y: &Hash, _schedule_default: Option<&Scheduler>) -> Resolver {
+    fn try_from_yaml(y: &Hash, _schedule_default: Option<&Scheduler>) -> Result<Resolver, Error> {
         let mut resolver = Resolver::new();
-        let mut items: Vec<(String, String)> = vec![];
-        y.iter().for_each(|o| {
-            let k = o.0.as_str().unwrap();
-            let v = o.1.as_str().unwrap();
-            items.push((k.to_string(), v.to_string()));
-        });
+        for (k, v) in y.iter() {
+            let engine = k.as_str().ok_or_else(|| Error::Schema("engine must be a string".to_string()))?;
+            let pointer = v.as_str().ok_or_else(|| Error::Schema(format!("pointer for '{}' must be a string", engine)))?;
+            let resolver_item = SimpleResolverItem::try_new(pointer.to_string())?;
+            resolver.add(engine.to_string(), Box::new(resolver_item))?;
+        };
 
-        for item in items {
-            let resolver_item = SimpleResolverItem::new(item.1);
-            let _r = resolver.add(item.0, Box::new(resolver_item));
+        Ok(resolver)
+    }
+}
+
+impl FromYaml<Resource> for Resource {
+    fn try_from_yaml(y: &Hash, _schedule_default: Option<&Scheduler>) -> Result<Resource, Error> {
+        let mut resource = Resource::new();
+        let pointers = match y.get(&Yaml::from_str("pointers")) {
+            Some(v) => as_hash(v, "pointers")?,
+            None => {
+                return Err(Error::Schema("missing 'pointers'".to_string()));
+            },
         };
+        for (k, v) in pointers.iter() {
+            let engine = k.as_str().ok_or_else(|| Error::Schema("engine must be a string".to_string()))?;
+            let pointer = v.as_str().ok_or_else(|| Error::Schema(format!("pointer for '{}' must be a string", engine)))?;
+            if let Err(e) = hex::decode(pointer) {
+                return Err(Error::Decode(format!("invalid hex pointer '{}': {}", pointer, e)));
+            }
+            resource.pointers.insert(engine.to_string(), pointer.to_string());
+        }
+
+        if let Some(v) = y.get(&Yaml::from_str("size")) {
+            let size = v.as_i64().filter(|x| *x >= 0).ok_or_else(|| Error::Schema("'size' must be a positive integer".to_string()))?;
+            resource.size = Some(size as u64);
+        }
+
+        if y.contains_key(&Yaml::from_str("content_type")) {
+            resource.content_type = Some(get_str(y, "content_type")?.to_string());
+        }
 
-        resolver
+        if y.contains_key(&Yaml::from_str("signature")) {
+            let signature = get_str(y, "signature")?;
+            let v = hex::decode(signature).map_err(|e| Error::Decode(format!("invalid hex signature '{}': {}", signature, e)))?;
+            resource.signature = Some(v);
+        }
+
+        Ok(resource)
     }
 }
 
+impl FromYaml<Manifest> for Manifest {
+    fn try_from_yaml(y: &Hash, _schedule_default: Option<&Scheduler>) -> Result<Manifest, Error> {
+        let mut manifest = Manifest::new();
+        let resources = match y.get(&Yaml::from_str("resources")) {
+            Some(v) => as_hash(v, "resources")?,
+            None => {
+                return Err(Error::Schema("missing 'resources'".to_string()));
+            },
+        };
+        for (k, v) in resources.iter() {
+            let name = k.as_str().ok_or_else(|| Error::Schema("resource name must be a string".to_string()))?;
+            let resource_y = as_hash(v, name)?;
+            let resource = Resource::try_from_yaml(resource_y, None).map_err(|e| {
+                match e {
+                    Error::Schema(s) => Error::Schema(format!("resource '{}': {}", name, s)),
+                    Error::Decode(s) => Error::Decode(format!("resource '{}': {}", name, s)),
+                    e => e,
+                }
+            })?;
+            manifest.resources.insert(name.to_string(), resource);
+        }
+        Ok(manifest)
+    }
+}
+
+/// Parse the first document of a YAML string as a mapping.
+///
+/// Panics if the string is not valid YAML or the document is not a mapping; see
+/// [try_yaml_from_str].
 pub fn yaml_from_str(s: &str) -> Hash {
-    let yaml_docs = YamlLoader::load_from_str(s).unwrap();
-    let y = yaml_docs[0].as_hash().unwrap();
-    y.clone()
+    try_yaml_from_str(s).unwrap()
+}
+
+/// Parse the first document of a YAML string as a mapping.
+pub fn try_yaml_from_str(s: &str) -> Result<Hash, Error> {
+    let yaml_docs = match YamlLoader::load_from_str(s) {
+        Ok(v) => v,
+        Err(e) => {
+            return Err(Error::Decode(e.to_string()));
+        },
+    };
+    match yaml_docs.first().and_then(|v| v.as_hash()) {
+        Some(y) => Ok(y.clone()),
+        None => Err(Error::Schema("document must be a mapping".to_string())),
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::yaml_from_str;
+    use super::{
+        yaml_from_str,
+        try_yaml_from_str,
+        FromYaml,
+    };
+    use crate::control::Controller;
+    use crate::control::graph::Stagger;
+    use crate::endpoint::Endpoint;
+    use crate::error::Error;
+    use crate::manifest::Manifest;
+    use crate::resolver::Resolver;
+    use crate::source::{
+        Source,
+        Start,
+    };
+    use crate::timing::Scheduler;
     use yaml_rust::{
         Yaml,
     };
@@ -227,4 +514,82 @@ bar: \n\
         let r = y.get(&k).unwrap().as_vec().unwrap();
         assert_eq!(r.len(), 2);
     }
+
+    #[test]
+    fn test_yaml_inherit_timing() {
+        let y = yaml_from_str("delay: 100\nsources:\n  - engine: foo\n    endpoints:\n      - url: https://one.foo.com\n      - url: https://two.foo.com\n");
+        let mut ctrl_yaml = Controller::from_yaml(&y, None);
+
+        let mut ctrl = Controller::new(Scheduler {
+            delay: 100,
+            timeout: 0,
+        });
+        let mut source = Source::new("foo".to_string());
+        source.endpoints.push(Endpoint::new("https://one.foo.com", None));
+        source.endpoints.push(Endpoint::new("https://two.foo.com", None));
+        ctrl.add(source);
+
+        let resolver = Resolver::from_yaml(&yaml_from_str("foo: deadbeef"), None);
+        let v_yaml: Vec<(u64, String, String)> = ctrl_yaml.generate(&resolver).into_iter().map(|v| (v.offset, v.url.to_string(), v.engine)).collect();
+        let v: Vec<(u64, String, String)> = ctrl.generate(&resolver).into_iter().map(|v| (v.offset, v.url.to_string(), v.engine)).collect();
+        assert_eq!(v_yaml.len(), 2);
+        assert_eq!(v_yaml, v);
+
+        let y = yaml_from_str("stagger: 1");
+        assert_eq!(Controller::from_yaml(&y, None).stagger, Stagger::Spacing(1));
+    }
+
+    #[test]
+    fn test_yaml_start() {
+        let y = yaml_from_str("sources:\n  - engine: foo\n    endpoints: []\n  - engine: bar\n    start:\n      after: foo\n      delay: 400\n    endpoints: []\n  - engine: baz\n    start: 42\n    priority: 1\n    endpoints: []\n");
+        let ctrl = Controller::from_yaml(&y, None);
+        assert_eq!(ctrl.sources[0].engine, "baz");
+        assert_eq!(ctrl.sources[0].start, Some(Start::Absolute(42)));
+        assert_eq!(ctrl.sources[2].start, Some(Start::After {
+            engine: "foo".to_string(),
+            delay: 400,
+        }));
+        assert_eq!(ctrl.offsets, vec![42, 0, 400]);
+
+        let y = yaml_from_str("sources:\n  - engine: bar\n    start:\n      after: foo\n    endpoints: []\n");
+        assert!(matches!(Controller::try_from_yaml(&y, None), Err(Error::UnknownEngine(_))));
+    }
+
+    #[test]
+    fn test_yaml_errors() {
+        assert!(matches!(try_yaml_from_str("- foo"), Err(Error::Schema(_))));
+        assert!(matches!(try_yaml_from_str("foo: [bar"), Err(Error::Decode(_))));
+
+        let y = yaml_from_str("delay: -1");
+        assert!(matches!(Controller::try_from_yaml(&y, None), Err(Error::Schema(_))));
+
+        let y = yaml_from_str("sources:\n  - engine: foo\n    endpoints:\n      - url: foo.com\n");
+        assert!(matches!(Controller::try_from_yaml(&y, None), Err(Error::InvalidUrl(_, _))));
+
+        let y = yaml_from_str("sources:\n  - endpoints: []\n");
+        assert!(matches!(Controller::try_from_yaml(&y, None), Err(Error::Schema(_))));
+
+        let y = yaml_from_str("foo: xyzzy");
+        assert!(matches!(Resolver::try_from_yaml(&y, None), Err(Error::Decode(_))));
+    }
+
+    #[test]
+    fn test_yaml_manifest() {
+        let y = yaml_from_str("resources:\n  index.html:\n    content_type: text/html\n    size: 1024\n    signature: '0102'\n    pointers:\n      foo: deadbeef\n      bar: beeffeed\n  app.js:\n    pointers:\n      foo: feedbeef\n");
+        let manifest = Manifest::from_yaml(&y, None);
+        assert_eq!(manifest.resources.len(), 2);
+        let resource = &manifest.resources["index.html"];
+        assert_eq!(resource.content_type.as_deref(), Some("text/html"));
+        assert_eq!(resource.size, Some(1024));
+        assert_eq!(resource.signature, Some(vec![1, 2]));
+        assert_eq!(resource.pointers["bar"], "beeffeed");
+        assert!(manifest.resources["app.js"].signature.is_none());
+
+        let y = yaml_from_str("resources:\n  app.js:\n    size: -1\n    pointers: {}\n");
+        let e = Manifest::try_from_yaml(&y, None).err().unwrap();
+        assert_eq!(e.to_string(), "schema error: resource 'app.js': 'size' must be a positive integer");
+
+        let y = yaml_from_str("resources:\n  app.js:\n    pointers:\n      foo: xyzzy\n");
+        assert!(matches!(Manifest::try_from_yaml(&y, None), Err(Error::Decode(_))));
+    }
 }
@@ -0,0 +1,1198 @@
+use std::collections::{
+    HashMap,
+    HashSet,
+};
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{
+    Path,
+    PathBuf,
+};
+use std::rc::Rc;
+
+use yaml_rust::{
+    ScanError,
+    Yaml,
+    YamlLoader,
+};
+use yaml_rust::parser::{
+    Event,
+    MarkedEventReceiver,
+    Parser,
+};
+use yaml_rust::scanner::Marker;
+use yaml_rust::yaml::Hash;
+
+use super::{
+    controller_from_yaml,
+    FromYaml,
+};
+use crate::control::Controller;
+use crate::endpoint::Endpoint;
+use crate::resolver::{
+    Resolver,
+    SimpleResolverItem,
+};
+use crate::validator::ValidatorRegistry;
+
+/// A problem found in a document by the [Loader].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    /// The file the entry was read from, if any.
+    pub file: Option<PathBuf>,
+    /// Path of the offending entry, e.g. `sources[1].endpoints[0].url`. Empty if the problem
+    /// concerns the document as a whole.
+    pub path: String,
+    pub message: String,
+    /// Line and column of the entry in the document, both counting from 1.
+    pub position: Option<(usize, usize)>,
+}
+
+impl Diagnostic {
+    fn new(path: &str, message: String, mark: Option<&Mark>) -> Diagnostic {
+        Diagnostic {
+            file: mark.and_then(|v| v.file.as_ref()).map(|v| v.to_path_buf()),
+            path: path.to_string(),
+            message,
+            position: mark.map(|v| (v.marker.line(), v.marker.col() + 1)),
+        }
+    }
+
+    fn for_file(file: Option<&Path>, message: String) -> Diagnostic {
+        Diagnostic {
+            file: file.map(|v| v.to_path_buf()),
+            path: String::new(),
+            message,
+            position: None,
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(v) = &self.file {
+            write!(f, "{}: ", v.display())?;
+        }
+        if !self.path.is_empty() {
+            write!(f, "{}: ", self.path)?;
+        }
+        write!(f, "{}", self.message)?;
+        if let Some((line, col)) = self.position {
+            write!(f, " at line {} column {}", line, col)?;
+        }
+        Ok(())
+    }
+}
+
+fn child(path: &str, k: &str) -> String {
+    match path.len() {
+        0 => k.to_string(),
+        _ => format!("{}.{}", path, k),
+    }
+}
+
+/// Returns the part of `path` below `prefix`, starting with `.` or `[` unless empty.
+fn below(path: &str, prefix: &str) -> Option<String> {
+    if prefix.is_empty() {
+        return match path.chars().next() {
+            None | Some('[') => Some(path.to_string()),
+            _ => Some(format!(".{}", path)),
+        };
+    }
+    let rest = path.strip_prefix(prefix)?;
+    match rest.chars().next() {
+        None | Some('.') | Some('[') => Some(rest.to_string()),
+        _ => None,
+    }
+}
+
+/// Copies the marks below the path `from` to the same entries below the path `to`.
+fn rebase(marks: &HashMap<String, Mark>, from: &str, to: &str, into: &mut HashMap<String, Mark>) {
+    for (k, v) in marks.iter() {
+        if let Some(rest) = below(k, from) {
+            let path = match to.len() {
+                0 => rest.strip_prefix('.').unwrap_or(&rest).to_string(),
+                _ => format!("{}{}", to, rest),
+            };
+            into.insert(path, v.clone());
+        }
+    }
+}
+
+fn entry_mut<'y>(y: &'y mut Yaml, k: &str) -> Option<&'y mut Yaml> {
+    match y {
+        Yaml::Hash(v) => v.get_mut(&Yaml::from_str(k)),
+        _ => None,
+    }
+}
+
+/// Looks up the environment variable `name` of the process.
+fn env_var(name: &str) -> Option<String> {
+    env::var(name).ok()
+}
+
+/// Substitutes `${NAME}` with the environment variable `NAME` as returned by `env`, and `{name}`
+/// with the entry `name` of `variables`. The `{pointer}` placeholders of a
+/// [Template](crate::endpoint::Template) are left as they are.
+fn interpolate(s: &str, variables: &HashMap<String, String>, env: fn(&str) -> Option<String>) -> Result<String, String> {
+    let mut r = String::new();
+    let mut rest = s;
+    while let Some(i) = rest.find('{') {
+        let j = match rest[i..].find('}') {
+            Some(v) => i + v,
+            None => {
+                return Err("unterminated placeholder".to_string());
+            },
+        };
+        let name = &rest[i + 1..j];
+        if rest[..i].ends_with('$') {
+            r.push_str(&rest[..i - 1]);
+            match env(name) {
+                Some(v) => r.push_str(&v),
+                None => {
+                    return Err(format!("environment variable '{}' is not set", name));
+                },
+            };
+        } else if name == "pointer" || name.starts_with("pointer[") {
+            r.push_str(&rest[..j + 1]);
+        } else {
+            r.push_str(&rest[..i]);
+            match variables.get(name) {
+                Some(v) => r.push_str(v),
+                None => {
+                    return Err(format!("unknown variable '{}'", name));
+                },
+            };
+        }
+        rest = &rest[j + 1..];
+    }
+    r.push_str(rest);
+    Ok(r)
+}
+
+/// The position of an entry, and the file it was read from.
+#[derive(Clone)]
+struct Mark {
+    file: Option<Rc<Path>>,
+    marker: Marker,
+}
+
+fn scan_diagnostic(e: &ScanError, file: Option<&Rc<Path>>) -> Diagnostic {
+    let s = e.to_string();
+    let message = match s.rsplit_once(" at line ") {
+        Some((v, _)) => v.to_string(),
+        None => s.clone(),
+    };
+    let mark = Mark {
+        file: file.cloned(),
+        marker: *e.marker(),
+    };
+    Diagnostic::new("", message, Some(&mark))
+}
+
+enum Frame {
+    Mapping {
+        path: String,
+        key: Option<String>,
+        keys: HashSet<String>,
+    },
+    Sequence {
+        path: String,
+        index: usize,
+    },
+}
+
+/// Records the position of every entry of each document by its path.
+///
+/// Positions of mapping values are those of their keys. Duplicate keys are reported here, since
+/// they are lost when the document is loaded.
+#[derive(Default)]
+struct Positions {
+    file: Option<Rc<Path>>,
+    stack: Vec<Frame>,
+    docs: Vec<HashMap<String, Mark>>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Positions {
+    fn mark(&mut self, path: String, marker: Marker) -> &mut Mark {
+        let file = &self.file;
+        self.docs.last_mut().unwrap().entry(path).or_insert_with(|| Mark {
+            file: file.clone(),
+            marker,
+        })
+    }
+
+    fn is_key(&self) -> bool {
+        matches!(self.stack.last(), Some(Frame::Mapping{key: None, ..}))
+    }
+
+    fn key(&mut self, k: String, mark: Marker) {
+        let (parent, first) = match self.stack.last_mut() {
+            Some(Frame::Mapping{path, key, keys}) => {
+                let first = keys.is_empty();
+                if !keys.insert(k.clone()) {
+                    let mark = Mark {
+                        file: self.file.clone(),
+                        marker: mark,
+                    };
+                    self.diagnostics.push(Diagnostic::new(&child(path, &k), format!("duplicate key '{}'", k), Some(&mark)));
+                }
+                *key = Some(k.clone());
+                (path.clone(), first)
+            },
+            _ => return,
+        };
+        // block mappings are only marked after their first key
+        if first {
+            let m = self.mark(parent.clone(), mark);
+            if m.marker.index() > mark.index() {
+                m.marker = mark;
+            }
+        }
+        self.mark(child(&parent, &k), mark);
+    }
+
+    fn node(&mut self, mark: Marker) -> String {
+        let path = match self.stack.last() {
+            Some(Frame::Mapping{path, key, ..}) => child(path, key.as_deref().unwrap_or("?")),
+            Some(Frame::Sequence{path, index}) => format!("{}[{}]", path, index),
+            None => String::new(),
+        };
+        self.mark(path.clone(), mark);
+        path
+    }
+
+    fn leave(&mut self) {
+        match self.stack.last_mut() {
+            Some(Frame::Mapping{key, ..}) => {
+                *key = match key {
+                    Some(_) => None,
+                    None => Some("?".to_string()),
+                };
+            },
+            Some(Frame::Sequence{index, ..}) => {
+                *index += 1;
+            },
+            None => {},
+        };
+    }
+}
+
+impl MarkedEventReceiver for Positions {
+    fn on_event(&mut self, ev: Event, mark: Marker) {
+        match ev {
+            Event::DocumentStart => {
+                self.stack.clear();
+                self.docs.push(HashMap::new());
+            },
+            Event::Scalar(v, ..) if self.is_key() => {
+                self.key(v, mark);
+            },
+            Event::Scalar(..) | Event::Alias(_) => {
+                self.node(mark);
+                self.leave();
+            },
+            Event::MappingStart(_) => {
+                let path = self.node(mark);
+                self.stack.push(Frame::Mapping {
+                    path,
+                    key: None,
+                    keys: HashSet::new(),
+                });
+            },
+            Event::SequenceStart(_) => {
+                let path = self.node(mark);
+                self.stack.push(Frame::Sequence {
+                    path,
+                    index: 0,
+                });
+            },
+            Event::MappingEnd | Event::SequenceEnd => {
+                self.stack.pop();
+                self.leave();
+            },
+            _ => {},
+        };
+    }
+}
+
+/// Checks a loaded document against the schema, collecting a [Diagnostic] for every problem.
+struct Checker<'r> {
+    marks: HashMap<String, Mark>,
+    diagnostics: Vec<Diagnostic>,
+    validators: &'r ValidatorRegistry,
+    variables: &'r HashMap<String, String>,
+    env: fn(&str) -> Option<String>,
+}
+
+impl<'r> Checker<'r> {
+    fn error(&mut self, path: &str, message: String) {
+        let mark = self.marks.get(path);
+        self.diagnostics.push(Diagnostic::new(path, message, mark));
+    }
+
+    fn missing(&mut self, path: &str, k: &str) {
+        let mark = self.marks.get(path);
+        self.diagnostics.push(Diagnostic::new(&child(path, k), "missing".to_string(), mark));
+    }
+
+    fn hash<'y>(&mut self, y: &'y Yaml, path: &str) -> Option<&'y Hash> {
+        let r = y.as_hash();
+        if r.is_none() {
+            self.error(path, "must be a mapping".to_string());
+        }
+        r
+    }
+
+    fn keys(&mut self, y: &Hash, path: &str, allowed: &[&str]) {
+        for k in y.keys() {
+            match k.as_str() {
+                Some(v) if allowed.contains(&v) => {},
+                Some(v) => self.error(&child(path, v), format!("unknown key '{}'", v)),
+                None => self.error(path, "keys must be strings".to_string()),
+            };
+        }
+    }
+
+    fn get_str<'y>(&mut self, y: &'y Hash, path: &str, k: &str) -> Option<&'y str> {
+        match y.get(&Yaml::from_str(k)) {
+            Some(v) => {
+                let r = v.as_str();
+                if r.is_none() {
+                    self.error(&child(path, k), "must be a string".to_string());
+                }
+                r
+            },
+            None => {
+                self.missing(path, k);
+                None
+            },
+        }
+    }
+
+    fn get_vec<'y>(&mut self, y: &'y Hash, path: &str, k: &str) -> Option<&'y Vec<Yaml>> {
+        let r = y.get(&Yaml::from_str(k)).and_then(|v| v.as_vec());
+        if r.is_none() {
+            match y.contains_key(&Yaml::from_str(k)) {
+                true => self.error(&child(path, k), "must be a list".to_string()),
+                false => self.missing(path, k),
+            };
+        }
+        r
+    }
+
+    fn integer(&mut self, y: &Yaml, path: &str, min: i64, max: i64) -> Option<i64> {
+        match y {
+            Yaml::Integer(x) if *x >= min && *x <= max => Some(*x),
+            _ => {
+                self.error(path, format!("must be an integer between {} and {}", min, max));
+                None
+            },
+        }
+    }
+
+    fn get_u32(&mut self, y: &Hash, path: &str, k: &str) {
+        if let Some(v) = y.get(&Yaml::from_str(k)) {
+            self.integer(v, &child(path, k), 0, u32::MAX as i64);
+        }
+    }
+
+    fn schedule(&mut self, y: &Hash, path: &str, allowed: &[&str]) {
+        self.keys(y, path, allowed);
+        self.get_u32(y, path, "delay");
+        self.get_u32(y, path, "timeout");
+    }
+
+    fn controller(&mut self, y: &Yaml) {
+        let y = match self.hash(y, "") {
+            Some(v) => v,
+            None => return,
+        };
+        self.schedule(y, "", &["delay", "timeout", "stagger", "sources"]);
+        self.get_u32(y, "", "stagger");
+
+        if !y.contains_key(&Yaml::from_str("sources")) {
+            return;
+        }
+        let mut engines = HashMap::new();
+        if let Some(sources) = self.get_vec(y, "", "sources") {
+            for (i, source) in sources.iter().enumerate() {
+                self.source(source, &format!("sources[{}]", i), &mut engines);
+            }
+        }
+    }
+
+    fn source(&mut self, y: &Yaml, path: &str, engines: &mut HashMap<String, String>) {
+        let y = match self.hash(y, path) {
+            Some(v) => v,
+            None => return,
+        };
+        self.keys(y, path, &["engine", "endpoints", "schedule", "start", "priority", "trusted_keys"]);
+
+        if let Some(endpoints) = self.get_vec(y, path, "endpoints") {
+            for (i, endpoint) in endpoints.iter().enumerate() {
+                self.endpoint(endpoint, &format!("{}.endpoints[{}]", path, i));
+            }
+        }
+
+        if y.contains_key(&Yaml::from_str("trusted_keys")) {
+            if let Some(keys) = self.get_vec(y, path, "trusted_keys") {
+                for (i, key) in keys.iter().enumerate() {
+                    let path = format!("{}.trusted_keys[{}]", path, i);
+                    match key.as_str().map(hex::decode) {
+                        Some(Ok(_)) => {},
+                        Some(Err(e)) => self.error(&path, format!("invalid hex key: {}", e)),
+                        None => self.error(&path, "must be a string".to_string()),
+                    };
+                }
+            }
+        }
+
+        if let Some(v) = y.get(&Yaml::from_str("schedule")) {
+            let path = child(path, "schedule");
+            if let Some(schedule) = self.hash(v, &path) {
+                self.schedule(schedule, &path, &["delay", "timeout"]);
+            }
+        }
+
+        match y.get(&Yaml::from_str("start")) {
+            Some(Yaml::Hash(start)) => {
+                let path = child(path, "start");
+                self.keys(start, &path, &["after", "delay"]);
+                self.get_u32(start, &path, "delay");
+                if let Some(engine) = self.get_str(start, &path, "after") {
+                    if !engines.contains_key(engine) {
+                        self.error(&child(&path, "after"), format!("unknown engine '{}', must be the engine of an earlier source", engine));
+                    }
+                }
+            },
+            Some(v) => {
+                self.integer(v, &child(path, "start"), 0, u32::MAX as i64);
+            },
+            None => {},
+        };
+
+        if let Some(v) = y.get(&Yaml::from_str("priority")) {
+            self.integer(v, &child(path, "priority"), i32::MIN as i64, i32::MAX as i64);
+        }
+
+        if let Some(engine) = self.get_str(y, path, "engine") {
+            match engines.get(engine) {
+                Some(v) => {
+                    let message = format!("duplicate engine '{}', first defined in {}", engine, v);
+                    self.error(&child(path, "engine"), message);
+                },
+                None => {
+                    engines.insert(engine.to_string(), path.to_string());
+                },
+            };
+        }
+    }
+
+    fn endpoint(&mut self, y: &Yaml, path: &str) {
+        let y = match self.hash(y, path) {
+            Some(v) => v,
+            None => return,
+        };
+        self.keys(y, path, &["url", "validator"]);
+        if let Some(url) = self.get_str(y, path, "url") {
+            let r = interpolate(url, self.variables, self.env).and_then(|v| {
+                Endpoint::try_new(&v, None).map(|_| ()).map_err(|e| e.to_string())
+            });
+            if let Err(e) = r {
+                self.error(&child(path, "url"), e);
+            }
+        }
+        if y.contains_key(&Yaml::from_str("validator")) {
+            if let Some(name) = self.get_str(y, path, "validator") {
+                if self.validators.get(name).is_none() {
+                    self.error(&child(path, "validator"), format!("unknown validator '{}'", name));
+                }
+            }
+        }
+    }
+
+    fn resolver(&mut self, y: &Yaml) {
+        let y = match self.hash(y, "") {
+            Some(v) => v,
+            None => return,
+        };
+        for (k, v) in y.iter() {
+            let engine = match k.as_str() {
+                Some(v) => v,
+                None => {
+                    self.error("", "engines must be strings".to_string());
+                    continue;
+                },
+            };
+            match v.as_str() {
+                Some(pointer) => {
+                    if let Err(e) = SimpleResolverItem::try_new(pointer.to_string()) {
+                        self.error(engine, e.to_string());
+                    }
+                },
+                None => self.error(engine, "must be a string".to_string()),
+            };
+        }
+    }
+}
+
+/// A parsed document with the marks of its entries.
+struct Document {
+    y: Yaml,
+    marks: HashMap<String, Mark>,
+}
+
+impl Document {
+    fn empty() -> Document {
+        Document {
+            y: Yaml::Null,
+            marks: HashMap::new(),
+        }
+    }
+}
+
+/// Parse all documents of a string, adding any duplicate keys found to `diagnostics`.
+fn parse(s: &str, file: Option<&Path>, diagnostics: &mut Vec<Diagnostic>) -> Result<Vec<Document>, Diagnostic> {
+    let mut positions = Positions {
+        file: file.map(Rc::from),
+        ..Default::default()
+    };
+    if let Err(e) = Parser::new(s.chars()).load(&mut positions, true) {
+        return Err(scan_diagnostic(&e, positions.file.as_ref()));
+    }
+    let docs = YamlLoader::load_from_str(s).map_err(|e| scan_diagnostic(&e, positions.file.as_ref()))?;
+    diagnostics.append(&mut positions.diagnostics);
+    Ok(docs.into_iter().zip(positions.docs).map(|(y, marks)| {
+        Document {
+            y,
+            marks,
+        }
+    }).collect())
+}
+
+/// Combines the documents of one or more streams into a single `control` and `content` document.
+struct Merge {
+    control: Hash,
+    sources: Vec<Yaml>,
+    content: Option<Hash>,
+    control_marks: HashMap<String, Mark>,
+    content_marks: HashMap<String, Mark>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Merge {
+    fn new() -> Merge {
+        Merge {
+            control: Hash::new(),
+            sources: vec![],
+            content: None,
+            control_marks: HashMap::new(),
+            content_marks: HashMap::new(),
+            diagnostics: vec![],
+        }
+    }
+
+    fn error(&mut self, path: &str, message: &str, mark: Option<&Mark>) {
+        self.diagnostics.push(Diagnostic::new(path, message.to_string(), mark));
+    }
+
+    fn file(&mut self, path: &Path) {
+        match fs::read_to_string(path) {
+            Ok(s) => {
+                let base = path.parent().unwrap_or_else(|| Path::new("."));
+                self.stream(&s, Some(path), base);
+            },
+            Err(e) => {
+                self.diagnostics.push(Diagnostic::for_file(Some(path), format!("cannot read: {}", e)));
+            },
+        };
+    }
+
+    /// Add a `control` document, optionally followed by a `content` document.
+    fn stream(&mut self, s: &str, file: Option<&Path>, base: &Path) {
+        let mut docs = match parse(s, file, &mut self.diagnostics) {
+            Ok(v) => v.into_iter(),
+            Err(e) => {
+                self.diagnostics.push(e);
+                return;
+            },
+        };
+        self.add_control(docs.next().unwrap_or_else(Document::empty), base);
+        if let Some(doc) = docs.next() {
+            self.add_content(doc);
+        }
+        if docs.next().is_some() {
+            self.diagnostics.push(Diagnostic::for_file(file, "unexpected document after the content document".to_string()));
+        }
+    }
+
+    fn add_control(&mut self, doc: Document, base: &Path) {
+        let Document{y, mut marks} = doc;
+        let y = match y {
+            // a fragment defining a single source
+            Yaml::Hash(v) if v.contains_key(&Yaml::from_str("engine")) => {
+                let mut source_marks = HashMap::new();
+                rebase(&marks, "", "sources[0]", &mut source_marks);
+                marks = source_marks;
+                let mut y = Hash::new();
+                y.insert(Yaml::from_str("sources"), Yaml::Array(vec![Yaml::Hash(v)]));
+                y
+            },
+            Yaml::Hash(v) => v,
+            _ => {
+                self.error("", "must be a mapping", marks.get(""));
+                return;
+            },
+        };
+        if let Some(v) = marks.get("") {
+            self.control_marks.entry(String::new()).or_insert_with(|| v.clone());
+        }
+
+        for (k, v) in y {
+            let key = match k.as_str() {
+                Some(v) => v.to_string(),
+                None => {
+                    self.error("", "keys must be strings", marks.get(""));
+                    continue;
+                },
+            };
+            if key == "sources" {
+                match v {
+                    Yaml::Array(sources) => {
+                        for (i, mut source) in sources.into_iter().enumerate() {
+                            let path = format!("sources[{}]", self.sources.len());
+                            let mut source_marks = HashMap::new();
+                            rebase(&marks, &format!("sources[{}]", i), &path, &mut source_marks);
+                            self.include(&mut source, &path, &mut source_marks, base);
+                            self.control_marks.extend(source_marks);
+                            self.sources.push(source);
+                        }
+                    },
+                    _ => self.error("sources", "must be a list", marks.get("sources")),
+                };
+                continue;
+            }
+            match self.control.get(&k) {
+                Some(x) if *x != v => {
+                    let message = match self.control_marks.get(&key).and_then(|v| v.file.as_ref()) {
+                        Some(f) => format!("conflicting value, first set in {}", f.display()),
+                        None => "conflicting value".to_string(),
+                    };
+                    self.error(&key, &message, marks.get(&key));
+                },
+                Some(_) => {},
+                None => {
+                    rebase(&marks, &key, &key, &mut self.control_marks);
+                    self.control.insert(k, v);
+                },
+            };
+        }
+    }
+
+    /// Replace the `include` entries of the endpoints of a source with the endpoints listed in
+    /// the included file.
+    fn include(&mut self, source: &mut Yaml, path: &str, marks: &mut HashMap<String, Mark>, base: &Path) {
+        let endpoints = match source {
+            Yaml::Hash(v) => match v.get_mut(&Yaml::from_str("endpoints")) {
+                Some(Yaml::Array(v)) => v,
+                _ => return,
+            },
+            _ => return,
+        };
+        let prefix = child(path, "endpoints");
+        let mut expanded = vec![];
+        let mut expanded_marks = HashMap::new();
+        for (i, endpoint) in endpoints.drain(..).enumerate() {
+            let endpoint_path = format!("{}[{}]", prefix, i);
+            let include = endpoint.as_hash()
+                .filter(|v| v.len() == 1)
+                .and_then(|v| v.get(&Yaml::from_str("include")))
+                .cloned();
+            let include = match include {
+                Some(v) => v,
+                None => {
+                    rebase(marks, &endpoint_path, &format!("{}[{}]", prefix, expanded.len()), &mut expanded_marks);
+                    expanded.push(endpoint);
+                    continue;
+                },
+            };
+
+            let include_path = child(&endpoint_path, "include");
+            let file = match include.as_str() {
+                Some(v) => base.join(v),
+                None => {
+                    self.error(&include_path, "must be a string", marks.get(&include_path));
+                    continue;
+                },
+            };
+            let s = match fs::read_to_string(&file) {
+                Ok(v) => v,
+                Err(e) => {
+                    let message = format!("cannot read {}: {}", file.display(), e);
+                    self.error(&include_path, &message, marks.get(&include_path));
+                    continue;
+                },
+            };
+            let doc = match parse(&s, Some(&file), &mut self.diagnostics) {
+                Ok(v) => v.into_iter().next().unwrap_or_else(Document::empty),
+                Err(e) => {
+                    self.diagnostics.push(e);
+                    continue;
+                },
+            };
+            match doc.y {
+                Yaml::Array(v) => {
+                    for (j, endpoint) in v.into_iter().enumerate() {
+                        rebase(&doc.marks, &format!("[{}]", j), &format!("{}[{}]", prefix, expanded.len()), &mut expanded_marks);
+                        expanded.push(endpoint);
+                    }
+                },
+                _ => {
+                    let mut e = Diagnostic::new("", "must be a list of endpoints".to_string(), doc.marks.get(""));
+                    e.file = Some(file);
+                    self.diagnostics.push(e);
+                },
+            };
+        }
+        *endpoints = expanded;
+        marks.retain(|k, _| !matches!(below(k, &prefix), Some(v) if v.starts_with('[')));
+        marks.extend(expanded_marks);
+    }
+
+    fn add_content(&mut self, doc: Document) {
+        let Document{y, marks} = doc;
+        let y = match y {
+            Yaml::Hash(v) => v,
+            _ => {
+                self.error("", "must be a mapping", marks.get(""));
+                return;
+            },
+        };
+        let content = self.content.get_or_insert_with(Hash::new);
+        for (k, v) in y {
+            match k.as_str() {
+                Some(key) if content.contains_key(&k) => {
+                    let message = match self.content_marks.get(key).and_then(|v| v.file.as_ref()) {
+                        Some(f) => format!("duplicate engine '{}', first defined in {}", key, f.display()),
+                        None => format!("duplicate engine '{}'", key),
+                    };
+                    self.diagnostics.push(Diagnostic::new(key, message, marks.get(key)));
+                },
+                Some(key) => {
+                    rebase(&marks, key, key, &mut self.content_marks);
+                    content.insert(k, v);
+                },
+                None => {
+                    content.insert(k, v);
+                },
+            };
+        }
+    }
+}
+
+/// The objects loaded from a `control` document and an optional `content` document.
+pub struct Config {
+    pub controller: Controller,
+    pub resolver: Option<Resolver>,
+}
+
+/// Loads yaml documents strictly.
+///
+/// Unlike [FromYaml], the [Loader] rejects unknown keys, and instead of stopping at the first
+/// problem it returns a [Diagnostic] for every problem found. This makes it suitable for checking
+/// documents before they are deployed.
+///
+/// The `validator` of an endpoint is the name of a validator in `validators`, which is attached
+/// to the [Endpoint]. Endpoints without a `validator` use the
+/// [NoopValidator](crate::validator::NoopValidator).
+///
+/// A stream may contain a `control` document followed by a `content` document, separated by
+/// `---`. Instead of an endpoint, the `endpoints` of a source may contain an `include` entry
+/// naming a file with a list of endpoints, relative to the including file. Included files cannot
+/// include other files.
+///
+/// The `url` of an endpoint may refer to environment variables as `${NAME}`, and to the entries
+/// of `variables` as `{name}`. Together with the placeholders of a
+/// [Template](crate::endpoint::Template), this allows for urls like
+/// `https://{region}.mirror.example/{pointer[0:2]}/{pointer}`. Environment variables are looked
+/// up with `env`, which reads the environment of the process unless replaced.
+pub struct Loader {
+    pub validators: ValidatorRegistry,
+    pub variables: HashMap<String, String>,
+    pub env: fn(&str) -> Option<String>,
+}
+
+impl Default for Loader {
+    fn default() -> Loader {
+        Loader::new()
+    }
+}
+
+impl Loader {
+    pub fn new() -> Loader {
+        Loader {
+            validators: ValidatorRegistry::new(),
+            variables: HashMap::new(),
+            env: env_var,
+        }
+    }
+
+    /// Load a [Controller] from a `control` document.
+    pub fn load_controller(&self, s: &str) -> Result<Controller, Vec<Diagnostic>> {
+        let mut merge = Merge::new();
+        match parse(s, None, &mut merge.diagnostics) {
+            Ok(v) => merge.add_control(v.into_iter().next().unwrap_or_else(Document::empty), Path::new(".")),
+            Err(e) => merge.diagnostics.push(e),
+        };
+        self.finish(merge).map(|v| v.controller)
+    }
+
+    /// Load a [Resolver] from a `content` document.
+    pub fn load_resolver(&self, s: &str) -> Result<Resolver, Vec<Diagnostic>> {
+        let mut merge = Merge::new();
+        match parse(s, None, &mut merge.diagnostics) {
+            Ok(v) => merge.add_content(v.into_iter().next().unwrap_or_else(Document::empty)),
+            Err(e) => merge.diagnostics.push(e),
+        };
+        self.finish(merge).map(|v| v.resolver.unwrap_or_else(Resolver::new))
+    }
+
+    /// Load a `control` document and an optional `content` document from a string.
+    ///
+    /// Included files are relative to the current directory.
+    pub fn load_str(&self, s: &str) -> Result<Config, Vec<Diagnostic>> {
+        let mut merge = Merge::new();
+        merge.stream(s, None, Path::new("."));
+        self.finish(merge)
+    }
+
+    /// Same as [Loader::load_str], reading the documents from `r`.
+    pub fn load_reader<R: io::Read>(&self, mut r: R) -> Result<Config, Vec<Diagnostic>> {
+        let mut s = String::new();
+        if let Err(e) = r.read_to_string(&mut s) {
+            return Err(vec![Diagnostic::for_file(None, format!("cannot read: {}", e))]);
+        }
+        self.load_str(&s)
+    }
+
+    /// Load the documents of a file, or of all `.yaml` and `.yml` files in a directory.
+    ///
+    /// The files of a directory are merged in alphabetical order. Their sources are added in
+    /// turn, and the settings of the `control` documents must not conflict. A file may also
+    /// consist of a single source, as in `engine: foo`, possibly followed by a `content`
+    /// document. Engines must not be defined more than once across the files.
+    pub fn load_path(&self, path: &Path) -> Result<Config, Vec<Diagnostic>> {
+        let mut merge = Merge::new();
+        if !path.is_dir() {
+            merge.file(path);
+            return self.finish(merge);
+        }
+
+        let mut files = vec![];
+        let entries = fs::read_dir(path).and_then(|v| v.collect::<Result<Vec<fs::DirEntry>, io::Error>>());
+        match entries {
+            Ok(v) => {
+                for entry in v {
+                    let file = entry.path();
+                    match file.extension().and_then(|v| v.to_str()) {
+                        Some("yaml") | Some("yml") if file.is_file() => files.push(file),
+                        _ => {},
+                    };
+                }
+            },
+            Err(e) => {
+                return Err(vec![Diagnostic::for_file(Some(path), format!("cannot read: {}", e))]);
+            },
+        };
+        if files.is_empty() {
+            return Err(vec![Diagnostic::for_file(Some(path), "no yaml files in directory".to_string())]);
+        }
+        files.sort();
+        for file in files {
+            merge.file(&file);
+        }
+        self.finish(merge)
+    }
+
+    fn finish(&self, merge: Merge) -> Result<Config, Vec<Diagnostic>> {
+        let mut control = merge.control;
+        control.insert(Yaml::from_str("sources"), Yaml::Array(merge.sources));
+        let mut control = Yaml::Hash(control);
+        let content = merge.content.map(Yaml::Hash);
+
+        let mut checker = Checker {
+            marks: merge.control_marks,
+            diagnostics: merge.diagnostics,
+            validators: &self.validators,
+            variables: &self.variables,
+            env: self.env,
+        };
+        checker.controller(&control);
+        if let Some(y) = &content {
+            checker.marks = merge.content_marks;
+            checker.resolver(y);
+        }
+        if !checker.diagnostics.is_empty() {
+            return Err(checker.diagnostics);
+        }
+        self.interpolate_urls(&mut control);
+
+        let ctrl = controller_from_yaml(control.as_hash().unwrap(), None, &self.validators).map_err(|e| {
+            vec![Diagnostic::new("", e.to_string(), None)]
+        })?;
+        let resolver = match content {
+            Some(y) => {
+                let resolver = Resolver::try_from_yaml(y.as_hash().unwrap(), None).map_err(|e| {
+                    vec![Diagnostic::new("", e.to_string(), None)]
+                })?;
+                Some(resolver)
+            },
+            None => None,
+        };
+        Ok(Config {
+            controller: ctrl,
+            resolver,
+        })
+    }
+
+    fn interpolate_urls(&self, y: &mut Yaml) {
+        let sources = match entry_mut(y, "sources") {
+            Some(Yaml::Array(v)) => v,
+            _ => return,
+        };
+        for source_y in sources.iter_mut() {
+            let endpoints = match entry_mut(source_y, "endpoints") {
+                Some(Yaml::Array(v)) => v,
+                _ => continue,
+            };
+            for endpoint_y in endpoints.iter_mut() {
+                if let Some(Yaml::String(url)) = entry_mut(endpoint_y, "url") {
+                    if let Ok(v) = interpolate(url, &self.variables, self.env) {
+                        *url = v;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    use super::{
+        Diagnostic,
+        Loader,
+    };
+    use crate::control::graph::Stagger;
+    use crate::mock::TestValidator;
+    use crate::source::Start;
+
+    const CONTROL: &str = "delay: 100
+stagger: 1
+sources:
+  - engine: foo
+    endpoints:
+      - url: https://one.foo.com
+  - engine: bar
+    priority: -1
+    start:
+      after: foo
+      delay: 50
+    schedule:
+      timeout: 200
+    endpoints:
+      - url: https://only.bar.com
+";
+
+    #[test]
+    fn test_loader_controller() {
+        let ctrl = Loader::new().load_controller(CONTROL).unwrap();
+        assert_eq!(ctrl.timing().delay, 100);
+        assert_eq!(ctrl.stagger, Stagger::Spacing(1));
+        assert_eq!(ctrl.sources[1].priority, -1);
+        assert_eq!(ctrl.sources[1].start, Some(Start::After {
+            engine: "foo".to_string(),
+            delay: 50,
+        }));
+        assert_eq!(ctrl.sources[1].timing.as_ref().unwrap().timeout, 200);
+
+        let resolver = Loader::new().load_resolver("foo: deadbeef\nbar: beeffeed\n").unwrap();
+        assert_eq!(resolver.pointer_for(&"bar".to_string()).unwrap(), "beeffeed");
+    }
+
+    #[test]
+    fn test_loader_diagnostics() {
+        let s = "delay: -1
+timeout: 99999999999
+sources:
+  - engine: foo
+    endpoints:
+      - url: https://one.foo.com
+      - url: one.foo.com
+  - engine: foo
+    start:
+      after: baz
+    endpoints:
+      - ur1: https://two.foo.com
+  - engine: bar
+";
+        let r = Loader::new().load_controller(s).err().unwrap();
+        let v: Vec<(&str, Option<(usize, usize)>)> = r.iter().map(|v| (v.path.as_str(), v.position)).collect();
+        assert_eq!(v, vec![
+            ("delay", Some((1, 1))),
+            ("timeout", Some((2, 1))),
+            ("sources[0].endpoints[1].url", Some((7, 9))),
+            ("sources[1].endpoints[0].ur1", Some((12, 9))),
+            ("sources[1].endpoints[0].url", Some((12, 9))),
+            ("sources[1].start.after", Some((10, 7))),
+            ("sources[1].engine", Some((8, 5))),
+            ("sources[2].endpoints", Some((13, 5))),
+        ]);
+        assert_eq!(r[0].to_string(), "delay: must be an integer between 0 and 4294967295 at line 1 column 1");
+        assert_eq!(r[6].message, "duplicate engine 'foo', first defined in sources[0]");
+        assert_eq!(r[7].to_string(), "sources[2].endpoints: missing at line 13 column 5");
+    }
+
+    #[test]
+    fn test_loader_document() {
+        let r = Loader::new().load_controller("foo: [bar").err().unwrap();
+        assert_eq!(r.len(), 1);
+        assert_eq!(r[0].path, "");
+        assert!(r[0].position.is_some());
+
+        let r = Loader::new().load_controller("").err().unwrap();
+        assert_eq!(r, vec![Diagnostic {
+            file: None,
+            path: "".to_string(),
+            message: "must be a mapping".to_string(),
+            position: None,
+        }]);
+
+        let r = Loader::new().load_resolver("foo: deadbeef\nbar: xyzzy\nfoo: beeffeed\n").err().unwrap();
+        let v: Vec<&str> = r.iter().map(|v| v.path.as_str()).collect();
+        assert_eq!(v, vec!["foo", "bar"]);
+        assert_eq!(r[0].message, "duplicate key 'foo'");
+        assert_eq!(r[0].position, Some((3, 1)));
+    }
+
+    #[test]
+    fn test_loader_validators() {
+        let s = "sources:
+  - engine: foo
+    trusted_keys:
+      - '0102'
+    endpoints:
+      - url: https://one.foo.com
+        validator: foov
+      - url: https://two.foo.com
+  - engine: bar
+    trusted_keys: [xyzzy]
+    endpoints:
+      - url: https://only.bar.com
+        validator: barv
+";
+        let mut loader = Loader::new();
+        loader.validators.add("foov", &TestValidator{});
+        let r = loader.load_controller(s).err().unwrap();
+        let v: Vec<String> = r.iter().map(|v| v.to_string()).collect();
+        assert_eq!(v, vec![
+            "sources[1].endpoints[0].validator: unknown validator 'barv' at line 13 column 9",
+            "sources[1].trusted_keys[0]: invalid hex key: Odd number of digits at line 10 column 20",
+        ]);
+
+        loader.validators.add("barv", &TestValidator{});
+        let s = s.replace("[xyzzy]", "[]");
+        let ctrl = loader.load_controller(&s).unwrap();
+        assert_eq!(ctrl.sources[0].trusted_keys, vec![vec![1, 2]]);
+        let digest = vec![1, 2, 3];
+        let content = vec![4, 5, 6];
+        assert!(!ctrl.sources[0].endpoints[0].validator.verify(&digest, Some(&content), None));
+        assert!(ctrl.sources[0].endpoints[1].validator.verify(&digest, Some(&content), None));
+        assert!(!ctrl.sources[1].endpoints[0].validator.verify(&digest, Some(&content), None));
+    }
+
+    #[test]
+    fn test_loader_stream() {
+        let s = format!("{}---\nfoo: deadbeef\nbar: beeffeed\n", CONTROL);
+        let config = Loader::new().load_str(&s).unwrap();
+        assert_eq!(config.controller.sources.len(), 2);
+        assert_eq!(config.resolver.unwrap().pointer_for(&"foo".to_string()).unwrap(), "deadbeef");
+
+        let config = Loader::new().load_reader(CONTROL.as_bytes()).unwrap();
+        assert!(config.resolver.is_none());
+
+        let s = format!("{}---\nfoo: xyzzy\n---\nfoo: deadbeef\n", CONTROL);
+        let r = Loader::new().load_str(&s).err().unwrap();
+        let v: Vec<String> = r.iter().map(|v| v.to_string()).collect();
+        assert_eq!(v, vec![
+            "unexpected document after the content document",
+            "foo: decode error: invalid hex pointer 'xyzzy': Odd number of digits at line 17 column 1",
+        ]);
+    }
+
+    #[test]
+    fn test_loader_interpolate() {
+        let s = "sources:
+  - engine: foo
+    endpoints:
+      - url: https://{region}.mirror.example/{pointer[0:2]}/{pointer}
+      - url: https://${MIRROR_HOST}/{tenant}
+";
+        let mut loader = Loader::new();
+        loader.env = |_| None;
+        loader.variables.insert("region".to_string(), "eu".to_string());
+        let r = loader.load_controller(s).err().unwrap();
+        let v: Vec<String> = r.iter().map(|v| v.to_string()).collect();
+        assert_eq!(v, vec![
+            "sources[0].endpoints[1].url: environment variable 'MIRROR_HOST' is not set at line 5 column 9",
+        ]);
+
+        loader.env = |name| match name {
+            "MIRROR_HOST" => Some("two.foo.com".to_string()),
+            _ => None,
+        };
+        let r = loader.load_controller(s).err().unwrap();
+        assert_eq!(r[0].message, "unknown variable 'tenant'");
+
+        loader.variables.insert("tenant".to_string(), "acme".to_string());
+        let ctrl = loader.load_controller(s).unwrap();
+        assert_eq!(ctrl.sources[0].endpoints[0].url_for("deadbeef"), "https://eu.mirror.example/de/deadbeef");
+        assert_eq!(ctrl.sources[0].endpoints[1].url_for("deadbeef"), "https://two.foo.com/acme/deadbeef");
+    }
+
+    #[test]
+    fn test_loader_merge() {
+        let dir = env::temp_dir().join(format!("fadfada-loader-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.yaml"), "delay: 100\nsources:\n  - engine: foo\n    endpoints:\n      - include: foo.list\n---\nfoo: deadbeef\n").unwrap();
+        fs::write(dir.join("b.yml"), "delay: 200\nsources:\n  - engine: foo\n    endpoints: []\n---\nfoo: beeffeed\n").unwrap();
+        fs::write(dir.join("c.yaml"), "engine: bar\nendpoints:\n  - include: bar.list\n").unwrap();
+        fs::write(dir.join("foo.list"), "- url: https://one.foo.com\n- url: foo.com\n").unwrap();
+        fs::write(dir.join("notes.txt"), "delay: 300").unwrap();
+
+        let r = Loader::new().load_path(&dir).err().unwrap();
+        let v: Vec<_> = r.iter().map(|v| {
+            let file = v.file.as_ref().unwrap().file_name().unwrap().to_string_lossy().to_string();
+            (file, v.path.as_str(), v.message.split(',').next().unwrap(), v.position)
+        }).collect();
+        assert_eq!(v[0], ("b.yml".to_string(), "delay", "conflicting value", Some((1, 1))));
+        assert!(r[0].message.ends_with(&format!("first set in {}", dir.join("a.yaml").display())));
+        assert_eq!(v[1].1, "foo");
+        assert_eq!(v[1].3, Some((6, 1)));
+        assert_eq!(&v[2].0, "c.yaml");
+        assert_eq!(v[2].1, "sources[2].endpoints[0].include");
+        assert_eq!(v[3], ("foo.list".to_string(), "sources[0].endpoints[1].url", "invalid url 'foo.com': relative URL without a base", Some((2, 3))));
+        assert_eq!(v[4], ("b.yml".to_string(), "sources[1].engine", "duplicate engine 'foo'", Some((3, 5))));
+        assert_eq!(r[4].message, "duplicate engine 'foo', first defined in sources[0]");
+        assert_eq!(r.len(), 5);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,71 @@
+extern crate fadfada;
+
+use std::{
+        env,
+        fs,
+        process,
+        sync::Arc,
+    };
+
+use url::Url;
+
+use fadfada::source::Source;
+use fadfada::control::Controller;
+use fadfada::timing::Scheduler;
+use fadfada::endpoint::Endpoint;
+use fadfada::resolver::Resolver;
+use fadfada::adapter::Registry;
+use fadfada::adapter::file::FileAdapter;
+use fadfada::exec::{
+    Runner,
+    Request,
+    RequestError,
+};
+
+mod mock;
+use mock::TestResolverItem;
+
+#[test]
+fn test_file_source_in_race() {
+    let dir = env::temp_dir().join(format!("fadfada-race-{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("010203"), b"cached").unwrap();
+    let base = Url::from_file_path(&dir).unwrap();
+
+    let mut source_remote = Source::new("remote".to_string());
+    source_remote.endpoints.push(Endpoint::new("http://localhost:1", None));
+    source_remote.timing = Some(Scheduler {
+        delay: 0,
+        timeout: 0,
+    });
+
+    let mut source_local = Source::new("local".to_string());
+    source_local.endpoints.push(Endpoint::new(base.as_str(), None));
+    source_local.timing = Some(Scheduler {
+        delay: 0,
+        timeout: 0,
+    });
+
+    let scheduler = Scheduler {
+        delay: 10,
+        timeout: 1000,
+    };
+    let mut ctrl = Controller::new(scheduler);
+    ctrl.add(source_remote);
+    ctrl.add(source_local);
+
+    let mut resolver = Resolver::new();
+    let mut _r = resolver.add("remote".to_string(), Box::new(TestResolverItem{key: vec![1, 2, 3]}));
+    _r = resolver.add("local".to_string(), Box::new(TestResolverItem{key: vec![1, 2, 3]}));
+
+    let mut adapters = Registry::new();
+    adapters.add_scheme("http", Arc::new(|_: &Request| Err(RequestError::NotFound)));
+    adapters.add_scheme("file", Arc::new(FileAdapter::new(Some(1024))));
+
+    let runner = Runner::new(ctrl.timing());
+    let r = runner.run(ctrl.generate(&resolver), &adapters).unwrap();
+    assert_eq!(r.engine, "local");
+    assert_eq!(r.content, b"cached");
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -1,5 +1,3 @@
-use hex;
-
 use fadfada::resolver::{
         ResolverItem,
         ResolverError,
@@ -11,13 +9,13 @@ pub struct TestResolverItem {
     pub key: Digest,
 }
 
-impl<'r> ResolverItem for TestResolverItem {
+impl ResolverItem for TestResolverItem {
     fn digest(&self) -> &Digest {
-        return &self.key;
+        &self.key
     }
 
     fn pointer(&self) -> String {
-        return hex::encode(&self.key);
+        hex::encode(&self.key)
     }
 
     fn signature(&self) -> Result<Signature, ResolverError> {
@@ -0,0 +1,113 @@
+#![cfg(feature = "serde")]
+
+use fadfada::control::Controller;
+use fadfada::control::graph::Stagger;
+use fadfada::endpoint::Endpoint;
+use fadfada::resolver::Resolver;
+use fadfada::source::Start;
+use fadfada::timing::Scheduler;
+
+const CONTROLLER_JSON: &str = r#"{
+    "delay": 100,
+    "timeout": 1000,
+    "stagger": 5,
+    "sources": [
+        {
+            "engine": "bar",
+            "start": {"after": "foo", "delay": 50},
+            "endpoints": [{"url": "https://only.bar.com"}]
+        },
+        {
+            "engine": "foo",
+            "priority": 1,
+            "trusted_keys": ["0102"],
+            "schedule": {"delay": 10, "timeout": 200},
+            "endpoints": [{"url": "https://one.foo.com"}, {"url": "https://two.foo.com"}]
+        }
+    ]
+}"#;
+
+#[test]
+fn test_serde_controller_json() {
+    let ctrl: Controller = serde_json::from_str(CONTROLLER_JSON).unwrap();
+    assert_eq!(ctrl.timing().delay, 100);
+    assert_eq!(ctrl.timing().timeout, 1000);
+    assert_eq!(ctrl.stagger, Stagger::Spacing(5));
+    assert_eq!(ctrl.sources.len(), 2);
+    assert_eq!(ctrl.sources[0].engine, "foo");
+    assert_eq!(ctrl.sources[0].trusted_keys, vec![vec![1, 2]]);
+    assert_eq!(ctrl.sources[0].timing.as_ref().unwrap().timeout, 200);
+    assert_eq!(ctrl.sources[0].endpoints[1].url.as_str(), "https://two.foo.com/");
+    assert_eq!(ctrl.sources[1].start, Some(Start::After{engine: "foo".to_string(), delay: 50}));
+    assert!(ctrl.sources[1].timing.is_none());
+
+    let s = serde_json::to_string(&ctrl).unwrap();
+    let ctrl_again: Controller = serde_json::from_str(&s).unwrap();
+    assert_eq!(serde_json::to_string(&ctrl_again).unwrap(), s);
+}
+
+#[test]
+fn test_serde_controller_toml() {
+    let s = r#"
+delay = 100
+timeout = 1000
+
+[[sources]]
+engine = "foo"
+start = 20
+
+[[sources.endpoints]]
+url = "https://one.foo.com"
+"#;
+    let ctrl: Controller = toml::from_str(s).unwrap();
+    assert_eq!(ctrl.stagger, Stagger::None);
+    assert_eq!(ctrl.sources[0].start, Some(Start::Absolute(20)));
+
+    // going through a value puts the tables after the plain values, as toml requires
+    let s = toml::Value::try_from(&ctrl).unwrap().to_string();
+    let ctrl_again: Controller = toml::from_str(&s).unwrap();
+    assert_eq!(ctrl_again.sources[0].start, Some(Start::Absolute(20)));
+    assert_eq!(ctrl_again.sources[0].endpoints[0].url.as_str(), "https://one.foo.com/");
+}
+
+#[test]
+fn test_serde_errors() {
+    let r: Result<Endpoint, _> = serde_json::from_str(r#"{"url": "not a url"}"#);
+    assert!(r.err().unwrap().to_string().contains("invalid url"));
+
+    let r: Result<Endpoint, _> = serde_json::from_str(r#"{"url": "https://foo.com/{pointer[0]}"}"#);
+    assert!(r.err().unwrap().to_string().contains("unknown placeholder '{pointer[0]}'"));
+
+    // variables are substituted by the yaml Loader only
+    let r: Result<Endpoint, _> = serde_json::from_str(r#"{"url": "https://{region}.foo.com/{pointer}"}"#);
+    assert!(r.err().unwrap().to_string().contains("variable '{region}' requires the yaml Loader"));
+
+    let s = r#"{"sources": [{"engine": "bar", "start": {"after": "foo"}, "endpoints": []}]}"#;
+    let r: Result<Controller, _> = serde_json::from_str(s);
+    assert!(r.err().unwrap().to_string().contains("unknown engine 'foo'"));
+
+    let r: Result<Resolver, _> = serde_json::from_str(r#"{"foo": "xyz"}"#);
+    assert!(r.is_err());
+}
+
+#[test]
+fn test_serde_endpoint_template() {
+    let s = r#"{"url":"https://mirror.example/{pointer[0:2]}/{pointer}"}"#;
+    let endpoint: Endpoint = serde_json::from_str(s).unwrap();
+    assert_eq!(endpoint.url_for("deadbeef"), "https://mirror.example/de/deadbeef");
+    assert_eq!(serde_json::to_string(&endpoint).unwrap(), s);
+}
+
+#[test]
+fn test_serde_resolver() {
+    let resolver: Resolver = serde_json::from_str(r#"{"foo": "deadbeef", "bar": "0123"}"#).unwrap();
+    assert_eq!(resolver.pointer_for(&"foo".to_string()).unwrap(), "deadbeef");
+    assert_eq!(serde_json::to_string(&resolver).unwrap(), r#"{"bar":"0123","foo":"deadbeef"}"#);
+}
+
+#[test]
+fn test_serde_scheduler() {
+    let scheduler: Scheduler = toml::from_str("delay = 13").unwrap();
+    assert_eq!(scheduler.delay, 13);
+    assert_eq!(scheduler.timeout, 0);
+}
@@ -1,10 +1,25 @@
+#![cfg(feature = "web2")]
+
 extern crate fadfada;
 
+use std::io::{
+    Read,
+    Write,
+};
+use std::net::TcpListener;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
 use fadfada::source::Source;
 use fadfada::control::Controller;
 use fadfada::timing::Scheduler;
 use fadfada::endpoint::Endpoint;
 use fadfada::resolver::Resolver;
+use fadfada::exec::{
+    Request,
+    RequestError,
+};
 
 #[test]
 #[cfg(feature = "web2")]
@@ -24,6 +39,8 @@ fn test_web2_create_graph() {
         endpoints: vec!(endpoint_a_one, endpoint_a_two),
         timing: Some(sched_a),
         engine: "foo".to_string(),
+        start: None,
+        priority: 0,
     };
 
     // set up second source with endpoints and schedule
@@ -37,6 +54,8 @@ fn test_web2_create_graph() {
         endpoints: vec!(endpoint_b_one),
         timing: Some(sched_b),
         engine: "bar".to_string(),
+        start: None,
+        priority: 0,
     };
 
     // generate control graph from endpoints and schedules 
@@ -65,3 +84,129 @@ fn test_web2_create_graph() {
         println!(">> {:?}", v);
     }
 }
+
+// sha256 of "foo"
+const FOO_SHA256: &str = "2c26b46b68ffc68ff99b453c1d30413413422d706483bfa0f98a5e886266e7ae";
+const FOO_PATH: &str = "/2c26b46b68ffc68ff99b453c1d30413413422d706483bfa0f98a5e886266e7ae";
+const FOO_TAMPERED_PATH: &str = "/tampered/2c26b46b68ffc68ff99b453c1d30413413422d706483bfa0f98a5e886266e7ae";
+
+fn serve(listener: TcpListener) {
+    for stream in listener.incoming() {
+        let mut stream = stream.unwrap();
+        let mut buf = [0; 1024];
+        let c = stream.read(&mut buf).unwrap();
+        let head = String::from_utf8_lossy(&buf[..c]).to_string();
+        let path = head.split(' ').nth(1).unwrap_or("").to_string();
+        thread::spawn(move || {
+            let response = match path.as_str() {
+                "/010203" | FOO_PATH => "HTTP/1.1 200 OK\r\nContent-Length: 3\r\nConnection: close\r\n\r\nfoo".to_string(),
+                FOO_TAMPERED_PATH => "HTTP/1.1 200 OK\r\nContent-Length: 3\r\nConnection: close\r\n\r\nbar".to_string(),
+                "/slow/010203" => {
+                    thread::sleep(Duration::from_millis(500));
+                    "HTTP/1.1 200 OK\r\nContent-Length: 3\r\nConnection: close\r\n\r\nbar".to_string()
+                },
+                _ => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+            };
+            let _r = stream.write_all(response.as_bytes());
+        });
+    }
+}
+
+#[test]
+fn test_web2_http_adapter() {
+    use fadfada::adapter::Adapter;
+    use fadfada::adapter::http::HttpAdapter;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    thread::spawn(move || serve(listener));
+
+    let adapter = HttpAdapter::new();
+    let request = Request::new(0, format!("{}/010203", base), "web2".to_string());
+    assert_eq!(adapter.fetch(&request).unwrap(), b"foo");
+
+    let request = Request::new(0, format!("{}/040506", base), "web2".to_string());
+    assert_eq!(adapter.fetch(&request), Err(RequestError::Status(404)));
+
+    let mut request = Request::new(0, format!("{}/slow/010203", base), "web2".to_string());
+    request.timeout = Some(Duration::from_millis(100));
+    assert_eq!(adapter.fetch(&request), Err(RequestError::Timeout));
+}
+
+#[test]
+fn test_web2_http_race() {
+    use fadfada::adapter::Registry;
+    use fadfada::adapter::http::HttpAdapter;
+    use fadfada::exec::Runner;
+    use fadfada::web2::Sha256ImmutableResolverItem;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    thread::spawn(move || serve(listener));
+
+    let mut source = Source::new("web2".to_string());
+    source.endpoints.push(Endpoint::new(&format!("{}/slow", base), None));
+    source.endpoints.push(Endpoint::new(&base, None));
+    source.timing = Some(Scheduler {
+        delay: 50,
+        timeout: 2000,
+    });
+    let mut c = Controller::new(Scheduler {
+        delay: 0,
+        timeout: 2000,
+    });
+    c.add(source);
+
+    let mut resolver = Resolver::new();
+    let mut _r = resolver.add("web2".to_string(), Box::new(Sha256ImmutableResolverItem::new(&[1, 2, 3], None)));
+
+    let mut adapters = Registry::new();
+    adapters.add_scheme("http", Arc::new(HttpAdapter::new()));
+
+    let runner = Runner::new(c.timing());
+    let r = runner.run(c.generate(&resolver), &adapters).unwrap();
+    assert_eq!(r.offset, 50);
+    assert_eq!(r.content, b"foo");
+}
+
+#[test]
+fn test_web2_http_tampered() {
+    use fadfada::adapter::Registry;
+    use fadfada::adapter::http::HttpAdapter;
+    use fadfada::exec::Runner;
+    use fadfada::web2::{
+        Sha256ImmutableResolverItem,
+        Sha256ImmutableValidator,
+    };
+
+    static VALIDATOR: Sha256ImmutableValidator = Sha256ImmutableValidator{};
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    thread::spawn(move || serve(listener));
+
+    let mut source = Source::new("web2".to_string());
+    source.endpoints.push(Endpoint::new(&format!("{}/tampered", base), Some(&VALIDATOR)));
+    source.endpoints.push(Endpoint::new(&base, Some(&VALIDATOR)));
+    source.timing = Some(Scheduler {
+        delay: 50,
+        timeout: 2000,
+    });
+    let mut c = Controller::new(Scheduler {
+        delay: 0,
+        timeout: 2000,
+    });
+    c.add(source);
+
+    let key = hex::decode(FOO_SHA256).unwrap();
+    let mut resolver = Resolver::new();
+    let mut _r = resolver.add("web2".to_string(), Box::new(Sha256ImmutableResolverItem::new(&key, None)));
+
+    let mut adapters = Registry::new();
+    adapters.add_scheme("http", Arc::new(HttpAdapter::new()));
+
+    let runner = Runner::new(c.timing());
+    let r = runner.run(c.generate(&resolver), &adapters).unwrap();
+    assert_eq!(r.offset, 50);
+    assert_eq!(r.content, b"foo");
+}
@@ -1,3 +1,5 @@
+#![cfg(feature = "yaml")]
+
 use std::{
         path,
         fs,
@@ -15,16 +17,36 @@ use fadfada::source::Source;
 use fadfada::endpoint::Endpoint;
 use fadfada::control::Controller;
 use fadfada::resolver::Resolver;
+use fadfada::manifest::Manifest;
 
 #[cfg(feature = "yaml")]
 use fadfada::yaml::{
     yaml_from_str,
     FromYaml,
 };
+#[cfg(feature = "yaml")]
+use fadfada::yaml::loader::Loader;
+
+use fadfada::resolver::{
+    Digest,
+    Signature,
+};
+use fadfada::validator::Validator;
 
 mod mock;
 use mock::TestResolverItem;
 
+/// Accepts or rejects all content.
+struct TestValidator {
+    accept: bool,
+}
+
+impl Validator for TestValidator {
+    fn verify(&self, _digest: &Digest, _content: Option<&Vec<u8>>, _signature: Option<&Signature>) -> bool {
+        self.accept
+    }
+}
+
 #[test]
 #[cfg(feature= "yaml")]
 fn test_yaml_scheduler() {
@@ -32,19 +54,19 @@ fn test_yaml_scheduler() {
     timeout: 42 \n\
 ";
 
-    let mut y = yaml_from_str(&s);
+    let mut y = yaml_from_str(s);
     let scheduler = Scheduler::from_yaml(&y, None);
     assert_eq!(scheduler.delay, 13);
     assert_eq!(scheduler.timeout, 42);
 
     s = "delay: 111\n";
-    y = yaml_from_str(&s);
+    y = yaml_from_str(s);
     let mut scheduler_overridden = Scheduler::from_yaml(&y, Some(&scheduler));
     assert_eq!(scheduler_overridden.delay, 111);
     assert_eq!(scheduler_overridden.timeout, 42);
 
     s = "timeout: 222\n";
-    y = yaml_from_str(&s);
+    y = yaml_from_str(s);
     scheduler_overridden = Scheduler::from_yaml(&y, Some(&scheduler));
     assert_eq!(scheduler_overridden.delay, 13);
     assert_eq!(scheduler_overridden.timeout, 222);
@@ -52,7 +74,7 @@ fn test_yaml_scheduler() {
     s = "delay: 333 \n\
 timeout: 444 \n\
 ";
-    y = yaml_from_str(&s);
+    y = yaml_from_str(s);
     scheduler_overridden = Scheduler::from_yaml(&y, Some(&scheduler));
     assert_eq!(scheduler_overridden.delay, 333);
     assert_eq!(scheduler_overridden.timeout, 444);
@@ -72,6 +94,14 @@ fn test_yaml_endpoint() {
     assert_eq!(endpoint.url_for(&resource), "https://foo.com/deadbeef");
 }
 
+#[test]
+#[cfg(feature= "yaml")]
+fn test_yaml_endpoint_variables() {
+    let y = yaml_from_str("url: https://{region}.foo.com/{pointer}\n");
+    let e = Endpoint::try_from_yaml(&y, None).err().unwrap();
+    assert!(e.to_string().contains("variable '{region}' requires the yaml Loader"));
+}
+
 #[test]
 #[cfg(feature= "yaml")]
 fn test_yaml_source() {
@@ -85,7 +115,7 @@ fn test_yaml_source() {
     let k = Yaml::from_str("sources");
     let sources_y = y.get(&k).unwrap().as_vec().unwrap();
     let source_y = sources_y[0].as_hash().unwrap();
-    let source = Source::from_yaml(&source_y, None);
+    let source = Source::from_yaml(source_y, None);
     
     let source_timing = source.timing.unwrap();
     assert_eq!(source_timing.delay, 22);
@@ -93,6 +123,28 @@ fn test_yaml_source() {
     assert_eq!(source.endpoints.len(), 2);
 }
 
+#[test]
+#[cfg(feature = "yaml")]
+fn test_yaml_source_validators() {
+    let yaml_src_path = path::Path::new(".")
+        .join("testdata")
+        .join("validators.yaml");
+
+    let s = fs::read_to_string(&yaml_src_path).unwrap();
+    let ctrl = Controller::from_yaml(&yaml_from_str(&s), None);
+    let source = &ctrl.sources[0];
+    let digest = vec![1, 2, 3];
+    let content = vec![4, 5, 6];
+    assert!(source.endpoints[0].validator.verify(&digest, Some(&content), None));
+    assert!(!source.endpoints[1].validator.verify(&digest, Some(&content), None));
+    assert!(source.endpoints[2].validator.verify(&digest, Some(&content), None));
+
+    let r = Loader::new().load_controller(&s).err().unwrap();
+    assert_eq!(r.len(), 1);
+    assert_eq!(r[0].path, "sources[0].endpoints[1].validator");
+    assert_eq!(r[0].message, "unknown validator 'xyzzy'");
+}
+
 #[test]
 #[cfg(feature = "yaml")]
 fn test_yaml_controller() {
@@ -116,12 +168,80 @@ fn test_yaml_controller() {
     _r = resolver.add("bar".to_string(), Box::new(resolver_item_bar));
 
     let ctrl_graph = ctrl.generate(&resolver);
-    ctrl_graph.for_each(|v| {
-        debug!("element {} {}", v.0, v.1);
+    ctrl_graph.iter().for_each(|v| {
+        debug!("element {} {}", v.offset, v.url);
         //assert_eq!(v.0, 13);
     });
 }
 
+#[test]
+#[cfg(feature = "yaml")]
+fn test_yaml_loader_validators() {
+    let yaml_src_path = path::Path::new(".")
+        .join("testdata")
+        .join("source.yaml");
+
+    let s = fs::read_to_string(&yaml_src_path).unwrap();
+    let mut loader = Loader::new();
+    let r = loader.load_controller(&s).err().unwrap();
+    assert_eq!(r.len(), 2);
+    assert_eq!(r[0].path, "sources[0].endpoints[0].validator");
+    assert_eq!(r[0].message, "unknown validator 'foov'");
+
+    loader.validators.add("foov", &TestValidator{accept: true});
+    loader.validators.add("barv", &TestValidator{accept: false});
+    let ctrl = loader.load_controller(&s).unwrap();
+    let digest = vec![1, 2, 3];
+    assert!(ctrl.sources[0].endpoints[0].validator.verify(&digest, None, None));
+    assert!(!ctrl.sources[0].endpoints[1].validator.verify(&digest, None, None));
+}
+
+#[test]
+#[cfg(feature = "yaml")]
+fn test_yaml_loader_dir() {
+    let yaml_src_path = path::Path::new(".")
+        .join("testdata")
+        .join("deploy");
+
+    let config = Loader::new().load_path(&yaml_src_path).unwrap();
+    let mut ctrl = config.controller;
+    assert_eq!(ctrl.timing().delay, 200);
+    assert_eq!(ctrl.timing().timeout, 4000);
+    let urls: Vec<&str> = ctrl.sources[0].endpoints.iter().map(|v| v.url.as_str()).collect();
+    assert_eq!(urls, vec!["https://one.foo.com/", "https://two.foo.com/", "https://three.foo.com/"]);
+
+    let resolver = config.resolver.unwrap();
+    let v: Vec<(u64, String)> = ctrl.generate(&resolver).iter().map(|v| (v.offset, v.url.to_string())).collect();
+    assert_eq!(v, vec![
+        (0, "https://one.foo.com/deadbeef".to_string()),
+        (200, "https://two.foo.com/deadbeef".to_string()),
+        (200, "https://only.bar.com/beeffeed".to_string()),
+        (400, "https://three.foo.com/deadbeef".to_string()),
+    ]);
+
+    let config = Loader::new().load_path(&yaml_src_path.join("foo.yaml")).unwrap();
+    assert_eq!(config.controller.sources[0].endpoints.len(), 3);
+}
+
+#[test]
+#[cfg(feature = "yaml")]
+fn test_yaml_manifest() {
+    let testdata = path::Path::new(".").join("testdata");
+
+    let s = fs::read_to_string(testdata.join("source.yaml")).unwrap();
+    let ctrl = Controller::from_yaml(&yaml_from_str(&s), None);
+    let s = fs::read_to_string(testdata.join("manifest.yaml")).unwrap();
+    let manifest = Manifest::from_yaml(&yaml_from_str(&s), None);
+
+    let v: Vec<String> = manifest.generate(&ctrl, "index.html").unwrap().iter().map(|v| v.url.to_string()).collect();
+    assert_eq!(v, vec!["http://foo.com/deadbeef", "https://xyzzy.net/beeffeed", "https://bar.com/baz/deadbeef"]);
+
+    let g = manifest.generate(&ctrl, "app.js").unwrap();
+    assert_eq!(g.len(), 2);
+    assert_eq!(g.skipped()[0].engine, "bar");
+    assert_eq!(manifest.resources["app.js"].content_type.as_deref(), Some("application/javascript"));
+}
+
 #[test]
 #[cfg(feature = "yaml")]
 fn test_yaml_resolver() {